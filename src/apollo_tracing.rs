@@ -0,0 +1,102 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApolloTracing {
+    pub version: u32,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub duration: u64,
+    pub execution: ApolloTracingExecution,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ApolloTracingExecution {
+    pub resolvers: Vec<ApolloTracingResolver>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApolloTracingResolver {
+    pub path: Vec<serde_json::Value>,
+    pub parent_type: String,
+    pub field_name: String,
+    pub return_type: String,
+    pub start_offset: u64,
+    pub duration: u64,
+}
+
+impl ApolloTracing {
+    pub fn from_extensions(
+        extensions: &BTreeMap<String, serde_json::Value>,
+    ) -> Option<Result<Self, serde_json::Error>> {
+        extensions
+            .get("tracing")
+            .filter(|tracing| tracing.is_object())
+            .map(|tracing| serde_json::from_value(tracing.clone()))
+    }
+
+    pub fn render_table(&self) -> String {
+        let mut resolvers = self.execution.resolvers.iter().collect::<Vec<_>>();
+        resolvers.sort_by_key(|resolver| std::cmp::Reverse(resolver.duration));
+
+        let rows = resolvers
+            .iter()
+            .map(|resolver| {
+                [
+                    format_duration(resolver.duration),
+                    format_duration(resolver.start_offset),
+                    format_path(&resolver.path),
+                    format!("{}.{}", resolver.parent_type, resolver.field_name),
+                    resolver.return_type.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let header = ["duration", "offset", "path", "field", "type"];
+        let mut widths = header.map(|column| column.len());
+        for row in rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let mut table = String::new();
+        let _ = writeln!(
+            table,
+            "tracing: total duration = {}, resolvers = {}",
+            format_duration(self.duration),
+            rows.len(),
+        );
+
+        for row in std::iter::once(header.map(String::from)).chain(rows) {
+            let line = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            let _ = writeln!(table, "{}", line.trim_end());
+        }
+
+        table
+    }
+}
+
+fn format_path(path: &[serde_json::Value]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            serde_json::Value::String(segment) => segment.clone(),
+            segment => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn format_duration(nanoseconds: u64) -> String {
+    if nanoseconds >= 1_000_000 {
+        format!("{:.3}ms", nanoseconds as f64 / 1_000_000.0)
+    } else {
+        format!("{:.3}us", nanoseconds as f64 / 1_000.0)
+    }
+}
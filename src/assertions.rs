@@ -0,0 +1,58 @@
+#[derive(Debug, Clone)]
+pub enum Assertion {
+    Equals {
+        path: String,
+        expected: serde_json::Value,
+    },
+    Exists {
+        path: String,
+    },
+    NoErrors,
+}
+
+impl Assertion {
+    pub fn check(&self, response: &serde_json::Value) -> bool {
+        match self {
+            Assertion::Equals { path, expected } => select(response, path) == Some(expected),
+            Assertion::Exists { path } => select(response, path).is_some(),
+            Assertion::NoErrors => response
+                .get("errors")
+                .and_then(|errors| errors.as_array())
+                .is_none_or(|errors| errors.is_empty()),
+        }
+    }
+}
+
+impl std::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assertion::Equals { path, expected } => write!(f, "{path} == {expected}"),
+            Assertion::Exists { path } => write!(f, "{path} exists"),
+            Assertion::NoErrors => write!(f, "no errors"),
+        }
+    }
+}
+
+// supports the member and index subset of jsonpath, e.g., $.data.users[0].name
+pub fn select<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+
+    let mut selected = value;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, indices) = match segment.find('[') {
+            Some(bracket_pos) => segment.split_at(bracket_pos),
+            None => (segment, ""),
+        };
+
+        if !key.is_empty() {
+            selected = selected.get(key)?;
+        }
+
+        for index in indices.split('[').filter(|index| !index.is_empty()) {
+            let index = index.strip_suffix(']')?.parse::<usize>().ok()?;
+            selected = selected.get(index)?;
+        }
+    }
+
+    Some(selected)
+}
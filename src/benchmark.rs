@@ -0,0 +1,186 @@
+use std::{fmt::Write, time::Duration};
+
+use futures_util::StreamExt;
+use reqwest::header::HeaderMap;
+
+use crate::client::{try_http_request, ClientOptions};
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkOptions {
+    pub requests: usize,
+    pub concurrency: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub requests: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub elapsed_ms: f64,
+    pub requests_per_second: f64,
+    pub latency_ms: LatencyPercentiles,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LatencyPercentiles {
+    pub min: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl BenchmarkReport {
+    pub fn render_table(&self) -> String {
+        let mut rendered = String::new();
+
+        let _ = writeln!(rendered, "requests      {}", self.requests);
+        let _ = writeln!(
+            rendered,
+            "errors        {} ({:.1}%)",
+            self.errors,
+            self.error_rate * 100.0
+        );
+        let _ = writeln!(rendered, "elapsed       {:.1}ms", self.elapsed_ms);
+        let _ = writeln!(
+            rendered,
+            "throughput    {:.1} req/s",
+            self.requests_per_second
+        );
+        for (name, latency) in [
+            ("min", self.latency_ms.min),
+            ("p50", self.latency_ms.p50),
+            ("p90", self.latency_ms.p90),
+            ("p99", self.latency_ms.p99),
+            ("max", self.latency_ms.max),
+        ] {
+            let _ = writeln!(rendered, "latency {name:<5} {latency:.1}ms");
+        }
+
+        rendered
+    }
+}
+
+// a request is failed if it cannot be sent, its status is not a success or it has errors
+pub async fn run_benchmark(
+    server_endpoint: &str,
+    headers: HeaderMap,
+    query: String,
+    operation_name: Option<String>,
+    variables: serde_json::Map<String, serde_json::Value>,
+    options: &BenchmarkOptions,
+) -> Result<BenchmarkReport, Box<dyn std::error::Error>> {
+    let client = reqwest::Client::new();
+    let client_options = ClientOptions::default();
+    let started_at = std::time::Instant::now();
+
+    // at most `concurrency` requests are in flight, the next one starts when any of them finishes
+    let results = futures_util::stream::iter(0..options.requests)
+        .map(|_index| {
+            let (client, headers, query, variables) =
+                (&client, headers.clone(), query.clone(), variables.clone());
+            let operation_name = operation_name.as_deref();
+            let client_options = &client_options;
+
+            async move {
+                let request_started_at = std::time::Instant::now();
+                let mut succeeded = false;
+                let result = try_http_request(
+                    client,
+                    server_endpoint,
+                    headers,
+                    Some(query),
+                    operation_name,
+                    variables,
+                    &mut |response, metadata| {
+                        succeeded = response.errors.is_empty()
+                            && metadata.status.is_some_and(|status| status.is_success());
+                        Ok(())
+                    },
+                    client_options,
+                )
+                .await;
+                if let Err(e) = result.as_ref() {
+                    tracing::debug!(error = %e, "benchmark request failed");
+                }
+
+                (request_started_at.elapsed(), result.is_ok() && succeeded)
+            }
+        })
+        .buffer_unordered(options.concurrency)
+        .collect::<Vec<_>>()
+        .await;
+    let elapsed = started_at.elapsed();
+
+    let errors = results
+        .iter()
+        .filter(|(_latency, succeeded)| !succeeded)
+        .count();
+    let mut latencies = results
+        .into_iter()
+        .map(|(latency, _succeeded)| latency)
+        .collect::<Vec<_>>();
+    latencies.sort();
+
+    Ok(BenchmarkReport {
+        requests: options.requests,
+        errors,
+        error_rate: errors as f64 / options.requests.max(1) as f64,
+        elapsed_ms: duration_ms(elapsed),
+        requests_per_second: options.requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency_ms: LatencyPercentiles {
+            min: duration_ms(latencies.first().copied().unwrap_or_default()),
+            p50: duration_ms(percentile(&latencies, 50.0)),
+            p90: duration_ms(percentile(&latencies, 90.0)),
+            p99: duration_ms(percentile(&latencies, 99.0)),
+            max: duration_ms(latencies.last().copied().unwrap_or_default()),
+        },
+    })
+}
+
+// nearest-rank percentile of the sorted latencies
+fn percentile(sorted_latencies: &[Duration], percentile: f64) -> Duration {
+    if sorted_latencies.is_empty() {
+        return Duration::default();
+    }
+
+    let rank = (percentile / 100.0 * sorted_latencies.len() as f64).ceil() as usize;
+
+    sorted_latencies[rank.clamp(1, sorted_latencies.len()) - 1]
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_of_known_latencies() {
+        let latencies = (1..=10).map(Duration::from_millis).collect::<Vec<_>>();
+
+        assert_eq!(percentile(&latencies, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&latencies, 50.0), Duration::from_millis(5));
+        assert_eq!(percentile(&latencies, 90.0), Duration::from_millis(9));
+        assert_eq!(percentile(&latencies, 95.0), Duration::from_millis(10));
+        assert_eq!(percentile(&latencies, 99.0), Duration::from_millis(10));
+        assert_eq!(percentile(&latencies, 100.0), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn percentile_of_no_latencies_is_zero() {
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn every_percentile_of_a_single_latency_is_that_latency() {
+        let latencies = [Duration::from_millis(7)];
+
+        for p in [0.0, 50.0, 99.0, 100.0] {
+            assert_eq!(percentile(&latencies, p), Duration::from_millis(7));
+        }
+    }
+}
@@ -3,8 +3,23 @@ use std::str::FromStr;
 use clap::{builder::TypedValueParser, error::ErrorKind, Arg, Command, Error};
 use http::{HeaderName, HeaderValue};
 
+use crate::{client::HeaderCommand, file_upload::FileUploadDir, request_signing::HmacSigner};
+
 #[derive(Debug, Clone)]
-pub struct ClapKeyJsonValueParser;
+pub struct ClapKeyJsonValueParser {
+    pub separator: String,
+    // the value is always parsed as json instead of guessing its type
+    pub strict_json: bool,
+}
+
+impl Default for ClapKeyJsonValueParser {
+    fn default() -> Self {
+        Self {
+            separator: "=".into(),
+            strict_json: false,
+        }
+    }
+}
 
 impl TypedValueParser for ClapKeyJsonValueParser {
     type Value = (String, serde_json::Value);
@@ -17,59 +32,86 @@ impl TypedValueParser for ClapKeyJsonValueParser {
     ) -> Result<Self::Value, Error> {
         let value = value.to_string_lossy();
 
-        let (variable_name, variable_value): (&str, serde_json::Value) = if let Some(equals_pos) =
-            value.find("=")
-        {
-            let (variable_name, variable_value) = value.split_at(equals_pos);
-
-            let variable_value = &variable_value[1..variable_value.len()];
-            let variable_value = if variable_value.is_empty() {
-                serde_json::Value::Null
-            } else if variable_value == "true" {
-                serde_json::Value::Bool(true)
-            } else if variable_value == "false" {
-                serde_json::Value::Bool(false)
-            } else if variable_value.starts_with("\"") && variable_value.ends_with("\"") {
-                serde_json::Value::String(variable_value[1..variable_value.len() - 1].to_string())
-            } else if let Ok(value) = variable_value.parse::<i128>() {
-                serde_json::Value::Number(serde_json::Number::from_i128(value).ok_or_else(
-                    || {
-                        cmd.clone().error(
-                            ErrorKind::InvalidValue,
-                            "cannot convert from i128 to serde_json::Number",
-                        )
-                    },
-                )?)
-            } else if let Ok(value) = variable_value.parse::<u128>() {
-                serde_json::Value::Number(serde_json::Number::from_u128(value).ok_or_else(
-                    || {
-                        cmd.clone().error(
-                            ErrorKind::InvalidValue,
-                            "cannot convert from u128 to serde_json::Number",
-                        )
-                    },
-                )?)
-            } else if let Ok(value) = variable_value.parse::<f64>() {
-                serde_json::Value::Number(serde_json::Number::from_f64(value).ok_or_else(|| {
+        if let Some((variable_name, variable_value)) = value.split_once(self.separator.as_str()) {
+            if let Some(variable_name) = variable_name.strip_suffix(":DateTime") {
+                return Ok((variable_name.into(), parse_date_time(cmd, variable_value)?));
+            }
+        }
+
+        if self.strict_json {
+            let (variable_name, variable_value) =
+                value.split_once(self.separator.as_str()).ok_or_else(|| {
                     cmd.clone().error(
                         ErrorKind::InvalidValue,
-                        "cannot convert from f64 to serde_json::Number",
+                        format!("expected a value like 'name{}\"text\"'", self.separator),
+                    )
+                })?;
+            let variable_value = serde_json::from_str(variable_value).map_err(|e| {
+                cmd.clone().error(
+                    ErrorKind::InvalidValue,
+                    format!("the value of '{variable_name}' is not valid json: {e}"),
+                )
+            })?;
+
+            return Ok((variable_name.into(), variable_value));
+        }
+
+        let (variable_name, variable_value): (&str, serde_json::Value) =
+            if let Some(separator_pos) = value.find(self.separator.as_str()) {
+                let (variable_name, variable_value) = value.split_at(separator_pos);
+
+                let variable_value = &variable_value[self.separator.len()..variable_value.len()];
+                let variable_value = if variable_value.is_empty() {
+                    serde_json::Value::Null
+                } else if variable_value == "true" {
+                    serde_json::Value::Bool(true)
+                } else if variable_value == "false" {
+                    serde_json::Value::Bool(false)
+                } else if variable_value.starts_with("\"") && variable_value.ends_with("\"") {
+                    serde_json::Value::String(
+                        variable_value[1..variable_value.len() - 1].to_string(),
                     )
-                })?)
-            } else if (variable_value.starts_with("[") && variable_value.ends_with("]"))
-                || (variable_value.starts_with("{") && variable_value.ends_with("}"))
-            {
-                serde_json::from_str(variable_value)
-                    .map_err(|e| cmd.clone().error(ErrorKind::InvalidValue, e.to_string()))?
+                } else if let Ok(value) = variable_value.parse::<i128>() {
+                    serde_json::Value::Number(serde_json::Number::from_i128(value).ok_or_else(
+                        || {
+                            cmd.clone().error(
+                                ErrorKind::InvalidValue,
+                                "cannot convert from i128 to serde_json::Number",
+                            )
+                        },
+                    )?)
+                } else if let Ok(value) = variable_value.parse::<u128>() {
+                    serde_json::Value::Number(serde_json::Number::from_u128(value).ok_or_else(
+                        || {
+                            cmd.clone().error(
+                                ErrorKind::InvalidValue,
+                                "cannot convert from u128 to serde_json::Number",
+                            )
+                        },
+                    )?)
+                } else if let Ok(value) = variable_value.parse::<f64>() {
+                    serde_json::Value::Number(serde_json::Number::from_f64(value).ok_or_else(
+                        || {
+                            cmd.clone().error(
+                                ErrorKind::InvalidValue,
+                                "cannot convert from f64 to serde_json::Number",
+                            )
+                        },
+                    )?)
+                } else if (variable_value.starts_with("[") && variable_value.ends_with("]"))
+                    || (variable_value.starts_with("{") && variable_value.ends_with("}"))
+                {
+                    serde_json::from_str(variable_value)
+                        .map_err(|e| cmd.clone().error(ErrorKind::InvalidValue, e.to_string()))?
+                } else {
+                    serde_json::Value::String(variable_value.to_string())
+                };
+
+                (variable_name, variable_value)
             } else {
-                serde_json::Value::String(variable_value.to_string())
+                (value.as_ref(), serde_json::Value::Null)
             };
 
-            (variable_name, variable_value)
-        } else {
-            (value.as_ref(), serde_json::Value::Null)
-        };
-
         Ok((variable_name.into(), variable_value))
     }
 }
@@ -104,3 +146,330 @@ impl TypedValueParser for ClapHttpHeaderParser {
         ))
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct ClapHeadersFileParser;
+
+impl TypedValueParser for ClapHeadersFileParser {
+    type Value = Vec<(HeaderName, HeaderValue)>;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let contents =
+            std::fs::read_to_string(value).map_err(|e| cmd.clone().error(ErrorKind::Io, e))?;
+        let invalid_header = |header_name: &str, reason: String| {
+            cmd.clone().error(
+                ErrorKind::ValueValidation,
+                format!("header '{header_name}': {reason}"),
+            )
+        };
+
+        parse_json_object(cmd, &contents)?
+            .into_iter()
+            .map(|(header_name, header_value)| {
+                // numbers and booleans are accepted, e.g., {"x-retry-count": 3}
+                let header_value = match header_value {
+                    serde_json::Value::String(header_value) => header_value,
+                    serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+                        header_value.to_string()
+                    }
+                    _ => {
+                        return Err(invalid_header(
+                            &header_name,
+                            "the value has to be a string, a number or a boolean".into(),
+                        ))
+                    }
+                };
+
+                Ok((
+                    HeaderName::from_str(&header_name)
+                        .map_err(|e| invalid_header(&header_name, e.to_string()))?,
+                    HeaderValue::from_str(&header_value)
+                        .map_err(|e| invalid_header(&header_name, e.to_string()))?,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapJsonObjectParser;
+
+impl TypedValueParser for ClapJsonObjectParser {
+    type Value = serde_json::Map<String, serde_json::Value>;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        parse_json_object(cmd, &value.to_string_lossy())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapJsonObjectFileParser;
+
+impl TypedValueParser for ClapJsonObjectFileParser {
+    type Value = serde_json::Map<String, serde_json::Value>;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let contents =
+            std::fs::read_to_string(value).map_err(|e| cmd.clone().error(ErrorKind::Io, e))?;
+
+        parse_json_object(cmd, &contents)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapVariableFileParser;
+
+impl TypedValueParser for ClapVariableFileParser {
+    type Value = (String, serde_json::Value);
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let value = value.to_string_lossy();
+
+        let (variable_path, path) = value
+            .split_once("=")
+            .filter(|(variable_path, path)| !variable_path.is_empty() && !path.is_empty())
+            .ok_or_else(|| {
+                cmd.clone().error(
+                    ErrorKind::InvalidValue,
+                    "expected a value like 'markdown=./README.md'",
+                )
+            })?;
+
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| cmd.clone().error(ErrorKind::Io, e))?;
+        let mut variable_value = if path.ends_with(".json") {
+            serde_json::from_str(&contents)
+                .map_err(|e| cmd.clone().error(ErrorKind::InvalidValue, e))?
+        } else {
+            serde_json::Value::String(contents)
+        };
+
+        // a dotted path like 'input.body' sets a nested key of the variable
+        let mut segments = variable_path.split('.').collect::<Vec<_>>();
+        let variable_name = segments.remove(0);
+        for segment in segments.into_iter().rev() {
+            variable_value = serde_json::json!({ segment: variable_value });
+        }
+
+        Ok((variable_name.to_string(), variable_value))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapCookieParser;
+
+impl TypedValueParser for ClapCookieParser {
+    type Value = (String, String);
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let value = value.to_string_lossy();
+
+        let (name, cookie_value) = value.split_once("=").ok_or_else(|| {
+            cmd.clone().error(
+                ErrorKind::InvalidValue,
+                "expected a value like 'session=abc123'",
+            )
+        })?;
+
+        // rfc 6265: the name is a token, the value consists of cookie-octets
+        let is_valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?={}".contains(c));
+        let is_valid_value = cookie_value
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !"\",;\\".contains(c));
+
+        if !is_valid_name || !is_valid_value {
+            return Err(cmd
+                .clone()
+                .error(ErrorKind::InvalidValue, format!("invalid cookie '{value}'")));
+        }
+
+        Ok((name.to_string(), cookie_value.to_string()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapFileUploadDirParser;
+
+impl TypedValueParser for ClapFileUploadDirParser {
+    type Value = FileUploadDir;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let value = value.to_string_lossy();
+
+        let (variable_path, dir) = value.split_once("=").ok_or_else(|| {
+            cmd.clone().error(
+                ErrorKind::InvalidValue,
+                "expected a value like 'files=./images'",
+            )
+        })?;
+
+        if variable_path.is_empty() || dir.is_empty() {
+            return Err(cmd.clone().error(
+                ErrorKind::InvalidValue,
+                "expected a value like 'files=./images'",
+            ));
+        }
+
+        Ok(FileUploadDir {
+            variable_path: variable_path.into(),
+            dir: dir.into(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapHeaderCommandParser;
+
+impl TypedValueParser for ClapHeaderCommandParser {
+    type Value = HeaderCommand;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let value = value.to_string_lossy();
+
+        let (header_name, command) = value.split_once("=").ok_or_else(|| {
+            cmd.clone().error(
+                ErrorKind::InvalidValue,
+                "expected a value like 'authorization=Bearer: print-token'",
+            )
+        })?;
+
+        let (value_prefix, command) = match command.split_once(": ") {
+            Some((value_prefix, command)) => (Some(value_prefix.to_string()), command),
+            None => (None, command),
+        };
+
+        if command.trim().is_empty() {
+            return Err(cmd
+                .clone()
+                .error(ErrorKind::InvalidValue, "the command must not be empty"));
+        }
+
+        Ok(HeaderCommand {
+            name: HeaderName::from_str(header_name)
+                .map_err(|e| cmd.clone().error(ErrorKind::ValueValidation, e))?,
+            value_prefix,
+            command: command.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClapHmacSignerParser;
+
+impl TypedValueParser for ClapHmacSignerParser {
+    type Value = HmacSigner;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, Error> {
+        let value = value.to_string_lossy();
+
+        let (header_name, secret_env_var) = value.split_once("=").ok_or_else(|| {
+            cmd.clone().error(
+                ErrorKind::InvalidValue,
+                "expected a value like 'x-signature=SIGNING_SECRET'",
+            )
+        })?;
+
+        // only the name of the environment variable is given, so the secret is not in the shell history
+        let secret = std::env::var_os(secret_env_var).ok_or_else(|| {
+            cmd.clone().error(
+                ErrorKind::ValueValidation,
+                format!("the environment variable '{secret_env_var}' is not set"),
+            )
+        })?;
+
+        Ok(HmacSigner {
+            header: HeaderName::from_str(header_name)
+                .map_err(|e| cmd.clone().error(ErrorKind::ValueValidation, e))?,
+            secret: secret.into_encoded_bytes(),
+        })
+    }
+}
+
+// an rfc3339 timestamp, 'now' or a duration relative to now (e.g., -1h), sent as a string
+fn parse_date_time(cmd: &Command, value: &str) -> Result<serde_json::Value, Error> {
+    let invalid = || {
+        cmd.clone().error(
+            ErrorKind::InvalidValue,
+            format!("expected an rfc3339 timestamp, 'now' or a relative duration like '-1h', got '{value}'"),
+        )
+    };
+
+    let now = chrono::Utc::now();
+    let date_time = match value.as_bytes().first() {
+        _ if value == "now" => now,
+        Some(sign @ (b'-' | b'+')) => {
+            let duration = humantime::parse_duration(&value[1..]).map_err(|_| invalid())?;
+            let duration = chrono::Duration::from_std(duration).map_err(|_| invalid())?;
+
+            if *sign == b'-' {
+                now.checked_sub_signed(duration).ok_or_else(invalid)?
+            } else {
+                now.checked_add_signed(duration).ok_or_else(invalid)?
+            }
+        }
+        // the given timestamp is sent unchanged, including its offset
+        _ => {
+            chrono::DateTime::parse_from_rfc3339(value).map_err(|_| invalid())?;
+            return Ok(serde_json::Value::String(value.to_string()));
+        }
+    };
+
+    Ok(serde_json::Value::String(
+        date_time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    ))
+}
+
+fn parse_json_object(
+    cmd: &Command,
+    value: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>, Error> {
+    match serde_json::from_str(value).map_err(|e| cmd.clone().error(ErrorKind::InvalidValue, e))? {
+        serde_json::Value::Object(object) => Ok(object),
+        _ => Err(cmd
+            .clone()
+            .error(ErrorKind::InvalidValue, "expected a json object")),
+    }
+}
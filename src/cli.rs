@@ -1,24 +1,95 @@
 use std::path::PathBuf;
 
-use clap::Parser;
-use graphql_cli_tools::clap_types::{ClapHttpHeaderParser, ClapKeyJsonValueParser};
+use clap::{Args, CommandFactory, FromArgMatches, Parser};
+use graphql_cli_tools::{
+    clap_types::{
+        ClapCookieParser, ClapFileUploadDirParser, ClapHeaderCommandParser, ClapHeadersFileParser,
+        ClapHmacSignerParser, ClapHttpHeaderParser, ClapJsonObjectFileParser, ClapJsonObjectParser,
+        ClapKeyJsonValueParser, ClapVariableFileParser,
+    },
+    client::{
+        DeadlineFormat, HeaderCommand, HttpMethod, HttpVersion, RequestEncoding,
+        VariablesPrecedence, WsProtocol,
+    },
+    file_upload::FileUploadDir,
+    operations::OperationKind,
+    request_signing::HmacSigner,
+    schema_diff::{DiffFormat, DiffSchemaOptions, SchemaSource},
+    trace_context::TraceId,
+};
 use reqwest::header::{HeaderName, HeaderValue};
+use tracing::level_filters::LevelFilter;
 
 #[derive(Debug, Parser)]
 pub struct ClientParams {
     #[arg(
         short('e'),
         long("server-endpoint"),
+        env("GRAPHQL_ENDPOINT"),
         help("Endpoint where the server accepts the connections (e.g., http://localhost:8000/api/graphql)"),
     )]
     pub server_endpoint: String,
 
+    #[arg(
+        long("auto-transport"),
+        help("Send subscriptions over WebSocket even if the server endpoint is an http(s) url (the url scheme is swapped unless --ws-endpoint is given)")
+    )]
+    pub auto_transport: bool,
+
+    #[arg(
+        long("ws-endpoint"),
+        env("GRAPHQL_WS_ENDPOINT"),
+        help("Endpoint used for subscriptions while queries and mutations go to --server-endpoint (e.g., ws://localhost:8000/api/graphql/ws)")
+    )]
+    pub ws_endpoint: Option<String>,
+
     #[arg(
         short('q'),
         long("query-path"),
-        help("Path of the query that has to be executed")
+        required_unless_present_any(["apq_id", "query"]),
+        help("Path of the query that has to be executed, a yaml or json object between two '---' lines at the top of the file provides default variables")
     )]
-    pub query_path: PathBuf,
+    pub query_path: Option<PathBuf>,
+
+    #[arg(
+        long("query"),
+        conflicts_with("query_path"),
+        help("Query to be executed given inline instead of a file (e.g., '{ __typename }')")
+    )]
+    pub query: Option<String>,
+
+    #[arg(
+        long("apq-id"),
+        conflicts_with_all(["query_path", "query"]),
+        help("Sha256 hash of a persisted query, the query itself is not sent (e.g., with --method get for CDN cached requests)")
+    )]
+    pub apq_id: Option<String>,
+
+    #[arg(
+        long("method"),
+        default_value("post"),
+        help("HTTP method of the request, with get the request is sent as url query parameters, any other method (e.g., put) sends the same json body as post")
+    )]
+    pub method: HttpMethod,
+
+    #[arg(
+        long("encoding"),
+        default_value("json"),
+        help("Encoding of the request body, form sends the query, operationName and the json stringified variables and extensions as application/x-www-form-urlencoded fields (ignored with --method get)")
+    )]
+    pub encoding: RequestEncoding,
+
+    #[arg(
+        long("resolve-imports"),
+        help("Resolve '# import \"./fragments.graphql\"' lines of the query relative to the importing file")
+    )]
+    pub resolve_imports: bool,
+
+    #[arg(
+        long("fragment-file"),
+        help("File of fragment definitions appended to the query (can be used multiple times, a fragment must not be defined twice)")
+    )]
+    pub fragment_files: Vec<PathBuf>,
 
     #[arg(
         short('o'),
@@ -27,20 +98,166 @@ pub struct ClientParams {
     )]
     pub operation_name: Option<String>,
 
+    #[arg(
+        long("infer-operation"),
+        requires("query_path"),
+        help("Without --operation-name, execute the operation named like the query file (e.g., GetUser for GetUser.graphql)")
+    )]
+    pub infer_operation: bool,
+
+    #[arg(
+        long("expect"),
+        help("Fail before sending anything if the selected operation is not of the given type")
+    )]
+    pub expect: Option<OperationKind>,
+
+    #[arg(
+        long("require-operation-name"),
+        help("Fail before sending anything if --operation-name is not given and the query has an anonymous operation")
+    )]
+    pub require_operation_name: bool,
+
+    #[arg(
+        long("no-operation-name"),
+        help("Omit the operationName key from the request body instead of sending null")
+    )]
+    pub no_operation_name: bool,
+
     #[arg(
         long("variables-from-json"),
-        help("Json file containing variables to be sent to the server")
+        help("Json or yaml (.yaml, .yml) file containing variables to be sent to the server, an array of objects executes the operation once per element (can be used multiple times, later files override the earlier ones)")
+    )]
+    pub variables_from_json: Vec<PathBuf>,
+
+    #[arg(
+        long("variables-ndjson"),
+        conflicts_with("variables_from_json"),
+        help("File with one json object of variables per line ('-' reads stdin), the operation is executed once per line")
+    )]
+    pub variables_ndjson: Option<PathBuf>,
+
+    #[arg(
+        long("fail-fast"),
+        help("Stop at the first failed HTTP request instead of continuing with the remaining variable sets")
+    )]
+    pub fail_fast: bool,
+
+    #[arg(
+        long("fail-on-error"),
+        help(
+            "Exit with code 1 if a response contains errors, the errors are also printed to stderr"
+        )
+    )]
+    pub fail_on_error: bool,
+
+    #[arg(
+        long("flatten-errors"),
+        help("Print every error of a response to stderr as its own json line, annotated with the operation name and the index of the error (replaces the errors printed by --fail-on-error)")
     )]
-    pub variables_from_json: Option<PathBuf>,
+    pub flatten_errors: bool,
+
+    #[arg(
+        long("partial-ok"),
+        requires("fail_on_error"),
+        help("Treat responses having both data and errors as successful with --fail-on-error")
+    )]
+    pub partial_ok: bool,
 
     #[arg(
         short('v'),
         long("variable"),
-        value_parser(ClapKeyJsonValueParser),
-        help("Variable to be sent to the server")
+        value_parser(ClapKeyJsonValueParser::default()),
+        help("Variable to be sent to the server, a ':DateTime' suffix of the name accepts an rfc3339 timestamp, 'now' or a duration relative to now (e.g., since:DateTime=-1h)")
     )]
     pub variables: Vec<(String, serde_json::Value)>,
 
+    #[arg(
+        long("var-separator"),
+        default_value("="),
+        value_parser(clap::builder::NonEmptyStringValueParser::new()),
+        help("Separator between the name and the value of the -v variables (e.g., ':=' for values containing '=')")
+    )]
+    pub var_separator: String,
+
+    #[arg(
+        long("strict-json"),
+        help("Parse the values of the -v variables as json instead of guessing their type, so strings have to be quoted (e.g., name='\"text\"')")
+    )]
+    pub strict_json: bool,
+
+    #[arg(
+        long("variable-file"),
+        value_parser(ClapVariableFileParser),
+        help("Variable whose value is the contents of a file, parsed if it is a .json file, otherwise sent as a string (e.g., markdown=./README.md, a dotted name sets a nested key and can be combined with --deep-merge)")
+    )]
+    pub variable_files: Vec<(String, serde_json::Value)>,
+
+    #[arg(
+        long("variables-precedence"),
+        default_value("cli-wins"),
+        help("Which side wins when a -v variable and a variables file set the same key")
+    )]
+    pub variables_precedence: VariablesPrecedence,
+
+    #[arg(
+        long("deep-merge"),
+        help("Merge nested objects of -v variables and the variables file key by key instead of replacing them wholesale")
+    )]
+    pub deep_merge: bool,
+
+    #[arg(
+        long("check-variables"),
+        help("Warn on stderr if variables are given but the selected operation declares none, so the server ignores them")
+    )]
+    pub check_variables: bool,
+
+    #[arg(
+        long("strict"),
+        help("Fail before sending anything instead of warning with --check-variables, and fail on an empty HTTP response instead of treating it as a response without data")
+    )]
+    pub strict: bool,
+
+    #[arg(
+        long("no-variables"),
+        conflicts_with("apply_defaults"),
+        help("Send no variables at all, the variables files and -v variables are ignored, fails if the operation has required variables")
+    )]
+    pub no_variables: bool,
+
+    #[arg(
+        long("apply-defaults"),
+        help("Send the default values declared in the operation for the variables that are not given")
+    )]
+    pub apply_defaults: bool,
+
+    #[arg(
+        long("file-dir"),
+        value_parser(ClapFileUploadDirParser),
+        help("Upload every file of a directory (sorted by name) as a multipart request into a list variable (e.g., files=./images)")
+    )]
+    pub file_upload_dirs: Vec<FileUploadDir>,
+
+    #[arg(
+        long("sort-variables"),
+        help("Sort the keys of the variables recursively, so identical variables always produce byte-identical requests")
+    )]
+    pub sort_variables: bool,
+
+    #[arg(
+        long("extensions-json"),
+        value_parser(ClapJsonObjectParser),
+        conflicts_with("extensions_from_json"),
+        help("Json object to be sent as the extensions of the request (e.g., '{\"key\": \"value\"}')")
+    )]
+    pub extensions_json: Option<serde_json::Map<String, serde_json::Value>>,
+
+    #[arg(
+        long("extensions-from-json"),
+        value_parser(ClapJsonObjectFileParser),
+        help("Json file containing the extensions to be sent with the request")
+    )]
+    pub extensions_from_json: Option<serde_json::Map<String, serde_json::Value>>,
+
     #[arg(
         long("http-header"),
         value_parser(ClapHttpHeaderParser),
@@ -48,24 +265,870 @@ pub struct ClientParams {
     )]
     pub headers: Vec<(HeaderName, HeaderValue)>,
 
+    #[arg(
+        long("headers-from-json"),
+        value_parser(ClapHeadersFileParser),
+        help("Json file containing an object of HTTP header names and values (can be used multiple times, a later file overrides the headers of the earlier ones, a header given with --http-header overrides the files)")
+    )]
+    pub headers_from_json: Vec<Vec<(HeaderName, HeaderValue)>>,
+
+    #[arg(
+        long("header-from-command"),
+        value_parser(ClapHeaderCommandParser),
+        help("HTTP header whose value is the output of a shell command, rerun before every attempt, an optional prefix is separated by ': ' (e.g., 'authorization=Bearer: gcloud auth print-access-token')")
+    )]
+    pub header_commands: Vec<HeaderCommand>,
+
+    #[arg(
+        long("sign-hmac"),
+        value_parser(ClapHmacSignerParser),
+        help("Header set to the hex encoded HMAC-SHA256 signature of the request body, the secret is read from the given environment variable (e.g., x-signature=SIGNING_SECRET)")
+    )]
+    pub hmac_signer: Option<HmacSigner>,
+
     #[arg(
         short('r'),
         long("try-reconnect-duration"),
         help("When in subscription mode, the client will try to reconnect to the server if there is no connection (e.g., 500ms"),
     )]
     pub try_reconnect_duration: Option<humantime::Duration>,
+
+    #[arg(
+        long("retry-on-graphql-error"),
+        value_name("CODES"),
+        value_delimiter(','),
+        help("Send an HTTP request again if its response has an error whose extensions.code is one of the given codes, the last error is reported as a failed attempt when the retries run out (e.g., SERVICE_UNAVAILABLE,TIMEOUT)")
+    )]
+    pub retry_on_graphql_error: Vec<String>,
+
+    #[arg(
+        long("retry-on-graphql-error-max"),
+        value_name("RETRIES"),
+        default_value("3"),
+        value_parser(clap::value_parser!(u32).range(..=100)),
+        requires("retry_on_graphql_error"),
+        help("Maximum number of retries of a request with --retry-on-graphql-error (at most 100)")
+    )]
+    pub retry_on_graphql_error_max: u32,
+
+    #[arg(
+        long("retry-on-graphql-error-backoff"),
+        default_value("500ms"),
+        requires("retry_on_graphql_error"),
+        help("Time to wait before the first retry with --retry-on-graphql-error, it is doubled after every retry up to 30s (e.g., 1s)")
+    )]
+    pub retry_on_graphql_error_backoff: humantime::Duration,
+
+    #[arg(
+        long("cache-ttl"),
+        requires("try_reconnect_duration"),
+        help("When polling over HTTP with --try-reconnect-duration, skip the requests identical to one that succeeded within the given time (e.g., 30s)")
+    )]
+    pub cache_ttl: Option<humantime::Duration>,
+
+    #[arg(
+        long("request-jitter"),
+        help("Sleep a random duration up to the given one before every HTTP request to spread out the requests of many instances (e.g., 200ms)")
+    )]
+    pub request_jitter: Option<humantime::Duration>,
+
+    #[arg(
+        long("connect-timeout"),
+        help("Maximum time of establishing the connection to the server, it does not limit the execution of the operation (e.g., 2s)")
+    )]
+    pub connect_timeout: Option<humantime::Duration>,
+
+    #[arg(
+        long("deadline"),
+        help("Maximum time of the whole request, the absolute deadline is also sent to the server in a header (e.g., 5s)")
+    )]
+    pub deadline: Option<humantime::Duration>,
+
+    #[arg(
+        long("max-response-size"),
+        help("Abort when an HTTP response body or a WebSocket message is larger than the given number of bytes")
+    )]
+    pub max_response_size: Option<usize>,
+
+    #[arg(
+        long("error-max-len"),
+        help(
+            "Truncate the logged errors of the failed attempts to the given number of characters"
+        )
+    )]
+    pub error_max_len: Option<usize>,
+
+    #[arg(
+        long("resume-field"),
+        help("Jsonpath of a cursor in the subscription events, the last seen value is sent in the --resume-variable variable when resubscribing after a reconnect (e.g., $.data.events.cursor)")
+    )]
+    pub resume_field: Option<String>,
+
+    #[arg(
+        long("resume-variable"),
+        default_value("cursor"),
+        requires("resume_field"),
+        help("Variable receiving the cursor captured by --resume-field")
+    )]
+    pub resume_variable: String,
+
+    #[arg(
+        long("cursor-file"),
+        requires("resume_field"),
+        help("File where the last cursor captured by --resume-field is saved, a saved cursor is sent in the --resume-variable variable of the first subscribe, so a restarted client resumes where it left off, only a single variable set is supported")
+    )]
+    pub cursor_file: Option<PathBuf>,
+
+    #[arg(
+        long("deadline-header"),
+        default_value("x-request-deadline"),
+        requires("deadline"),
+        help("Name of the header carrying the deadline")
+    )]
+    pub deadline_header: HeaderName,
+
+    #[arg(
+        long("deadline-format"),
+        default_value("rfc3339"),
+        requires("deadline"),
+        help("Format of the deadline header value")
+    )]
+    pub deadline_format: DeadlineFormat,
+
+    #[arg(
+        long("trace-id"),
+        num_args(0..=1),
+        help("Send a w3c traceparent header with the given trace id (32 hex digits) or a random one if no value is given, every attempt gets a new span id")
+    )]
+    pub trace_id: Option<Option<TraceId>>,
+
+    #[arg(
+        long("tracestate"),
+        requires("trace_id"),
+        help("Value of the w3c tracestate header sent along with the traceparent (e.g., vendor=value)")
+    )]
+    pub tracestate: Option<HeaderValue>,
+
+    #[arg(
+        long("ws-protocol"),
+        default_value("graphql-transport-ws"),
+        help("WebSocket subprotocol, auto offers both and uses the one selected by the server")
+    )]
+    pub ws_protocol: WsProtocol,
+
+    #[arg(
+        long("ws-subscription-id"),
+        value_parser(clap::builder::NonEmptyStringValueParser::new()),
+        help("Fixed id of the WebSocket subscribe message instead of a random uuid (suffixed with the index of the variable set when there are several)")
+    )]
+    pub ws_subscription_id: Option<String>,
+
+    #[arg(
+        long("no-ws-compression"),
+        help("Do not offer the permessage-deflate extension in the WebSocket handshake")
+    )]
+    pub no_ws_compression: bool,
+
+    #[arg(
+        long("ws-passthrough-types"),
+        value_delimiter(','),
+        help("Non-standard WebSocket message types forwarded as events with the whole message under extensions.wsMessage (e.g., ka,pong)")
+    )]
+    pub ws_passthrough_types: Vec<String>,
+
+    #[arg(
+        long("http-version"),
+        default_value("auto"),
+        help("HTTP version of the requests, 1.1 never negotiates h2, 2 uses h2 with prior knowledge (over http:// the server has to support cleartext h2c), auto negotiates it with alpn")
+    )]
+    pub http_version: HttpVersion,
+
+    #[arg(
+        long("no-decompress"),
+        help("Do not decompress gzip, brotli or deflate encoded HTTP responses, an encoded body is printed as it is received without being parsed")
+    )]
+    pub no_decompress: bool,
+
+    #[arg(
+        long("no-conditional"),
+        help("Do not send the ETag of the previous response as If-None-Match when polling with --method get, a 304 response is not printed otherwise")
+    )]
+    pub no_conditional: bool,
+
+    #[arg(
+        long("show-size"),
+        help("Print the content length, the size of the read body and the content encoding of every HTTP response to stderr, the content length and encoding of a compressed response are only known with --no-decompress")
+    )]
+    pub show_size: bool,
+
+    #[arg(
+        long("emit-lifecycle"),
+        help("Print json events to stderr when the client is connecting, connected, subscribed, receives an event, reconnecting, completed, closed by the server (with the close code and reason) or fails")
+    )]
+    pub emit_lifecycle: bool,
+
+    #[arg(
+        long("cookie"),
+        value_parser(ClapCookieParser),
+        help("Cookie to be sent in the WebSocket handshake (e.g., session=abc123), multiple cookies are joined into one header")
+    )]
+    pub ws_cookies: Vec<(String, String)>,
+
+    #[arg(
+        long("cacert"),
+        help("Pem file with additional CA certificates trusted for HTTPS and WSS, it takes precedence over --cacert-from-env")
+    )]
+    pub cacert: Option<PathBuf>,
+
+    #[arg(
+        long("cacert-from-env"),
+        help("Also trust the CA certificates of the SSL_CERT_FILE file and the SSL_CERT_DIR directories, ignored if --cacert is given")
+    )]
+    pub cacert_from_env: bool,
+
+    #[arg(
+        long("print-handshake"),
+        help("Print the status, negotiated subprotocol and relevant headers of the WebSocket handshake as json to stderr")
+    )]
+    pub print_handshake: bool,
+
+    #[arg(
+        long("data-key"),
+        default_value("data"),
+        help("Key of the response object that contains the data (for servers with a non-standard envelope)")
+    )]
+    pub data_key: String,
+
+    #[arg(
+        long("errors-key"),
+        default_value("errors"),
+        help("Key of the response object that contains the errors (for servers with a non-standard envelope)")
+    )]
+    pub errors_key: String,
+
+    #[arg(
+        long("show-tracing"),
+        help("Print a per-resolver timing table to stderr if the response contains Apollo tracing data in its extensions")
+    )]
+    pub show_tracing: bool,
+
+    #[arg(
+        long("save-response"),
+        help("Overwrite the given file with every response, variables like '-v token=@response:$.data.login.token' are read from this file before each operation")
+    )]
+    pub save_response: Option<PathBuf>,
+
+    #[arg(
+        long("mask"),
+        value_name("NAME"),
+        help("Header or variable (also a field of an input object) whose value is replaced with *** in the logs, the real value is sent (can be used multiple times, authorization, cookie and proxy-authorization are always masked)")
+    )]
+    pub mask: Vec<String>,
+
+    #[arg(
+        long("tee"),
+        help("Append every printed response to the given file as well")
+    )]
+    pub tee: Option<PathBuf>,
+
+    #[arg(
+        long("pipe-to"),
+        value_name("COMMAND"),
+        help("Write every response to the stdin of the given shell command instead of stdout, the command is started once and waited for at the end")
+    )]
+    pub pipe_to: Option<String>,
+
+    #[arg(
+        long("error-context"),
+        help("Print the query lines referenced by the locations of the returned errors to stderr")
+    )]
+    pub error_context: bool,
+
+    #[arg(
+        long("metrics-port"),
+        help("Serve request, error, event and reconnect counters in Prometheus text format on the given port of --metrics-bind")
+    )]
+    pub metrics_port: Option<u16>,
+
+    #[arg(
+        long("metrics-bind"),
+        default_value("127.0.0.1"),
+        requires("metrics_port"),
+        help("Address the metrics are served on, 0.0.0.0 exposes them on every interface")
+    )]
+    pub metrics_bind: std::net::IpAddr,
+
+    #[arg(
+        long("assert"),
+        value_parser(ClapKeyJsonValueParser::default()),
+        help("Fail with a non-zero exit code if the value at the given path of the response differs (e.g., '$.data.user.id=\"1\"')")
+    )]
+    pub assert_equals: Vec<(String, serde_json::Value)>,
+
+    #[arg(
+        long("assert-exists"),
+        help("Fail with a non-zero exit code if the given path does not exist in the response (e.g., '$.data.users[0]')")
+    )]
+    pub assert_exists: Vec<String>,
+
+    #[arg(
+        long("assert-no-errors"),
+        help("Fail with a non-zero exit code if the response contains errors")
+    )]
+    pub assert_no_errors: bool,
+
+    #[arg(
+        long("response-schema"),
+        value_name("JSON_SCHEMA_PATH"),
+        help("Validate the data of every response against the given JSON Schema, each violation is printed to stderr and the exit code is non-zero")
+    )]
+    pub response_schema: Option<PathBuf>,
+
+    #[arg(
+        long("summary"),
+        help("Print a one-line summary (status code, data keys, errors, elapsed time) to stderr after each HTTP response")
+    )]
+    pub summary: bool,
+
+    #[arg(
+        long("label"),
+        help("Wrap each response as {\"operation\": <operation name>, \"response\": <response>}")
+    )]
+    pub label: bool,
+
+    #[arg(
+        long("with-request"),
+        help("Wrap each response as {\"request\": <endpoint, operation name, variables hash, timestamp>, \"response\": <response>}")
+    )]
+    pub with_request: bool,
+
+    #[arg(
+        long("dedup"),
+        help("Suppress the responses and subscription events that are identical to a recently printed one")
+    )]
+    pub dedup: bool,
+
+    #[arg(
+        long("dedup-window"),
+        default_value("1"),
+        requires("dedup"),
+        value_parser(clap::value_parser!(u32).range(1..)),
+        help("Number of recently printed payloads --dedup compares against")
+    )]
+    pub dedup_window: u32,
+
+    #[arg(
+        long("output-template"),
+        help("Print each response as the given template, {jsonpath} placeholders are replaced with the selected values (e.g., 'user {data.user.id}: {data.user.name}')")
+    )]
+    pub output_template: Option<String>,
+
+    #[arg(
+        long("strict-template"),
+        requires("output_template"),
+        help("Fail when a placeholder of --output-template selects nothing instead of rendering it empty")
+    )]
+    pub strict_template: bool,
+
+    #[arg(
+        long("head"),
+        value_name("N"),
+        help("Print only the first N bytes of each response (after --output-template is applied), a truncated response ends with '…'")
+    )]
+    pub head: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+pub struct ListOperationsParams {
+    #[arg(
+        short('q'),
+        long("query-path"),
+        help("Path of the document whose operations have to be listed")
+    )]
+    pub query_path: PathBuf,
+
+    #[arg(long("format"), default_value("text"), help("Output format"))]
+    pub format: OutputFormat,
+}
+
+// the options shared by diff-schema and diff-endpoints
+#[derive(Debug, Args)]
+pub struct DiffOptionsParams {
+    #[arg(
+        long("summary-json"),
+        help("Write the number of changes per severity and whether there is a breaking change to the given json file")
+    )]
+    pub summary_json: Option<PathBuf>,
+
+    #[arg(
+        long("type"),
+        help("Only report the changes of the given type (can be used multiple times)")
+    )]
+    pub types: Vec<String>,
+
+    #[arg(
+        long("root"),
+        ignore_case(true),
+        help("Only report the changes of the given root operation type")
+    )]
+    pub root: Option<OperationKind>,
+
+    #[arg(
+        long("detect-renames"),
+        help("Report a removed and an added type of the same kind with mostly the same members as a rename")
+    )]
+    pub detect_renames: bool,
+
+    #[arg(
+        long("no-summary"),
+        help("Do not print the line with the number of changes per severity after the changes")
+    )]
+    pub no_summary: bool,
+
+    #[arg(
+        long("format"),
+        default_value("text"),
+        help("Format of the reported changes, github prints workflow commands rendered as annotations (error for breaking changes, warning otherwise)")
+    )]
+    pub format: DiffFormat,
+}
+
+impl From<DiffOptionsParams> for DiffSchemaOptions {
+    fn from(params: DiffOptionsParams) -> Self {
+        Self {
+            summary_json: params.summary_json,
+            types: params.types,
+            root: params.root,
+            detect_renames: params.detect_renames,
+            no_summary: params.no_summary,
+            format: params.format,
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
 pub struct DiffSchemaParams {
-    #[arg()]
-    pub schema_source_left: PathBuf,
-    #[arg()]
-    pub schema_source_right: PathBuf,
+    #[arg(help("Schema file, or git:<revision>:<path> to read it from a git revision (e.g., git:HEAD~1:schema.graphql)"))]
+    pub schema_source_left: SchemaSource,
+    #[arg(help("Schema file, or git:<revision>:<path> to read it from a git revision (e.g., git:HEAD~1:schema.graphql)"))]
+    pub schema_source_right: SchemaSource,
+
+    #[arg(
+        long("dirs"),
+        help("Both sources are directories, the files with the same name are diffed concurrently, the exit code is 1 if any pair has a breaking change")
+    )]
+    pub dirs: bool,
+
+    #[command(flatten)]
+    pub diff_options: DiffOptionsParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct DiffEndpointsParams {
+    #[arg(help(
+        "Endpoint whose schema is the left side of the diff (e.g., the production server)"
+    ))]
+    pub left_endpoint: String,
+    #[arg(help("Endpoint whose schema is the right side of the diff (e.g., the staging server)"))]
+    pub right_endpoint: String,
+
+    #[arg(
+        long("left-http-header"),
+        value_parser(ClapHttpHeaderParser),
+        help("HTTP header to be sent to the left endpoint")
+    )]
+    pub left_headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long("right-http-header"),
+        value_parser(ClapHttpHeaderParser),
+        help("HTTP header to be sent to the right endpoint")
+    )]
+    pub right_headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long("federation"),
+        help("Fetch the sdl of federated subgraphs with the _service query, introspection is used if it is not available")
+    )]
+    pub federation: bool,
+
+    #[arg(
+        long("introspection-query"),
+        help("File containing the query sent instead of the default introspection query (e.g., one without the fields a server rejects), its result has to contain the __schema")
+    )]
+    pub introspection_query: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub diff_options: DiffOptionsParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct IntrospectionToSdlParams {
+    #[arg(help("Json file containing the result of an introspection query"))]
+    pub introspection_path: PathBuf,
+
+    #[arg(
+        long("sort"),
+        help("Sort the definitions, fields and enum values by name")
+    )]
+    pub sort: bool,
+
+    #[arg(
+        long("include-builtins"),
+        help("Also print the built-in scalars, directives and the introspection types")
+    )]
+    pub include_builtins: bool,
+
+    #[arg(long("no-descriptions"), help("Omit the descriptions"))]
+    pub no_descriptions: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct PingParams {
+    #[arg(
+        short('e'),
+        long("server-endpoint"),
+        env("GRAPHQL_ENDPOINT"),
+        help("Endpoint where the server accepts the connections (e.g., http://localhost:8000/api/graphql)")
+    )]
+    pub server_endpoint: String,
+
+    #[arg(
+        long("http-header"),
+        value_parser(ClapHttpHeaderParser),
+        help("HTTP header to be sent to the server")
+    )]
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long("repeat"),
+        default_value("1"),
+        value_parser(clap::value_parser!(u32).range(1..)),
+        help("Number of pings, a summary of the success rate is printed after the last one")
+    )]
+    pub repeat: u32,
+
+    #[arg(
+        long("interval"),
+        default_value("1s"),
+        help("Time to wait between two pings (e.g., 500ms)")
+    )]
+    pub interval: humantime::Duration,
+}
+
+#[derive(Debug, Parser)]
+pub struct ExtractOperationParams {
+    #[arg(
+        short('q'),
+        long("query-path"),
+        help("Path of the document containing the operation")
+    )]
+    pub query_path: PathBuf,
+
+    #[arg(
+        short('o'),
+        long("operation-name"),
+        help("Name of the operation to be extracted")
+    )]
+    pub operation_name: String,
+
+    #[arg(
+        long("output"),
+        help("File to write the operation and its fragments to instead of stdout")
+    )]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CompletionsParams {
+    #[arg(long("shell"), help("Shell whose completion script is printed"))]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportCompletionsParams {
+    #[arg(help("Json file containing the result of an introspection query"))]
+    pub introspection_path: PathBuf,
+
+    #[arg(
+        long("root"),
+        ignore_case(true),
+        help("Only export the root type of the given operation type and its fields")
+    )]
+    pub root: Option<OperationKind>,
 }
 
 #[derive(Debug, Parser)]
-pub enum Cli {
-    Client(ClientParams),
+pub struct BenchmarkParams {
+    #[arg(
+        short('e'),
+        long("server-endpoint"),
+        env("GRAPHQL_ENDPOINT"),
+        help("Endpoint where the server accepts the connections (e.g., http://localhost:8000/api/graphql)")
+    )]
+    pub server_endpoint: String,
+
+    #[arg(
+        short('q'),
+        long("query-path"),
+        help("Path of the query that is sent by every request")
+    )]
+    pub query_path: PathBuf,
+
+    #[arg(
+        short('o'),
+        long("operation-name"),
+        help("Name of the operation that has to be executed")
+    )]
+    pub operation_name: Option<String>,
+
+    #[arg(
+        short('v'),
+        long("variable"),
+        value_parser(ClapKeyJsonValueParser::default()),
+        help("Variable to be sent to the server")
+    )]
+    pub variables: Vec<(String, serde_json::Value)>,
+
+    #[arg(
+        long("http-header"),
+        value_parser(ClapHttpHeaderParser),
+        help("HTTP header to be sent to the server")
+    )]
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        short('n'),
+        long("requests"),
+        default_value("100"),
+        value_parser(clap::value_parser!(u32).range(1..)),
+        help("Number of requests to be sent")
+    )]
+    pub requests: u32,
+
+    #[arg(
+        short('c'),
+        long("concurrency"),
+        default_value("10"),
+        value_parser(clap::value_parser!(u32).range(1..)),
+        help("Maximum number of requests in flight at the same time")
+    )]
+    pub concurrency: u32,
+
+    #[arg(
+        long("format"),
+        default_value("text"),
+        help(
+            "Format of the report with the latency percentiles, the throughput and the error rate"
+        )
+    )]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    Client(Box<ClientParams>),
     DiffSchema(DiffSchemaParams),
+    ListOperations(ListOperationsParams),
+    IntrospectionToSdl(IntrospectionToSdlParams),
+    Ping(PingParams),
+    DiffEndpoints(DiffEndpointsParams),
+    Completions(CompletionsParams),
+    ExtractOperation(ExtractOperationParams),
+    ExportCompletions(ExportCompletionsParams),
+    Benchmark(BenchmarkParams),
+}
+
+#[derive(Debug, Parser)]
+pub struct Cli {
+    #[arg(
+        long("log-level"),
+        global(true),
+        help("Log level (error, warn, info, debug, trace, off), overrides RUST_LOG")
+    )]
+    pub log_level: Option<LevelFilter>,
+
+    #[arg(
+        long("log-format"),
+        global(true),
+        default_value("text"),
+        help("Format of the log lines written to stderr")
+    )]
+    pub log_format: OutputFormat,
+
+    #[arg(
+        long("env-file"),
+        global(true),
+        help("File of KEY=VALUE lines loaded into the environment before the arguments are parsed, so it can provide GRAPHQL_ENDPOINT, the variables already set are not overridden")
+    )]
+    pub env_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// the env file has to be loaded before clap reads the environment variables, so it is looked up first
+pub fn parse_cli() -> Result<Cli, CannotLoadEnvFile> {
+    let args = std::env::args_os().collect::<Vec<_>>();
+    if let Some(env_file) = option_value(&args, "--env-file") {
+        dotenvy::from_path(env_file).map_err(|e| CannotLoadEnvFile {
+            path: env_file.into(),
+            error: e.to_string(),
+        })?;
+    }
+
+    Ok(try_parse_cli_from(args).unwrap_or_else(|e| e.exit()))
+}
+
+// the parser of the -v values depends on --var-separator and --strict-json, so they are taken from a
+// first pass that keeps the -v values as they are
+fn try_parse_cli_from(args: Vec<std::ffi::OsString>) -> Result<Cli, clap::Error> {
+    let first_pass = Cli::command()
+        .mut_subcommand("client", |client| {
+            client.mut_arg("variables", |arg| {
+                arg.value_parser(clap::builder::StringValueParser::new())
+            })
+        })
+        .try_get_matches_from(&args);
+    // a failed first pass fails the second one as well, which reports the error
+    let client_matches = first_pass
+        .as_ref()
+        .ok()
+        .and_then(|matches| matches.subcommand_matches("client"));
+    let strict_json = client_matches.is_some_and(|matches| matches.get_flag("strict_json"));
+    let var_separator = client_matches
+        .and_then(|matches| matches.get_one::<String>("var_separator"))
+        .cloned()
+        .unwrap_or_else(|| "=".to_string());
+
+    let command = Cli::command().mut_subcommand("client", |client| {
+        client.mut_arg("variables", |arg| {
+            arg.value_parser(ClapKeyJsonValueParser {
+                separator: var_separator,
+                strict_json,
+            })
+        })
+    });
+
+    Cli::from_arg_matches(&command.try_get_matches_from(args)?)
+}
+
+fn option_value<'a>(args: &'a [std::ffi::OsString], name: &str) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(index, arg)| {
+        let arg = arg.to_str()?;
+        match arg.strip_prefix(name) {
+            Some("") => args.get(index + 1)?.to_str(),
+            Some(value) => value.strip_prefix("="),
+            None => None,
+        }
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotLoadEnvFile: path = '{path}', error = {error}")]
+pub struct CannotLoadEnvFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completions_have_the_subcommands_flags_and_values() {
+        let mut completions = Vec::new();
+        clap_complete::generate(
+            clap_complete::Shell::Bash,
+            &mut Cli::command(),
+            "graphql-cli-tools",
+            &mut completions,
+        );
+        let completions = String::from_utf8(completions).unwrap();
+
+        assert!(completions.contains("diff-schema"));
+        assert!(completions.contains("--server-endpoint"));
+        assert!(completions.contains("graphql-transport-ws"));
+    }
+
+    fn client_variables(args: &[&str]) -> Result<Vec<(String, serde_json::Value)>, clap::Error> {
+        let args = [
+            "graphql-cli-tools",
+            "client",
+            "--server-endpoint",
+            "http://localhost",
+        ]
+        .iter()
+        .chain(args)
+        .map(std::ffi::OsString::from)
+        .collect();
+
+        match try_parse_cli_from(args)?.command {
+            Command::Client(params) => Ok(params.variables),
+            command => panic!("unexpected command: {command:?}"),
+        }
+    }
+
+    #[test]
+    fn strict_json_rejects_barewords() {
+        let result = client_variables(&["--query", "{ a }", "--strict-json", "-v", "name=text"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn strict_json_accepts_quoted_json() {
+        let variables =
+            client_variables(&["--query", "{ a }", "--strict-json", "-v", r#"name="text""#])
+                .unwrap();
+
+        assert_eq!(
+            variables,
+            vec![("name".to_string(), serde_json::json!("text"))]
+        );
+    }
+
+    #[test]
+    fn strict_json_in_a_value_is_not_the_flag() {
+        let variables = client_variables(&[
+            "--query=--strict-json",
+            "-v",
+            "name=text",
+            "-v",
+            "flag=--strict-json",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            variables,
+            vec![
+                ("name".to_string(), serde_json::json!("text")),
+                ("flag".to_string(), serde_json::json!("--strict-json")),
+            ]
+        );
+    }
+
+    #[test]
+    fn var_separator_is_applied_to_the_variables() {
+        let variables = client_variables(&[
+            "--query",
+            "{ a }",
+            "--var-separator",
+            ":=",
+            "-v",
+            "url:=a=b",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            variables,
+            vec![("url".to_string(), serde_json::json!("a=b"))]
+        );
+    }
 }
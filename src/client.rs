@@ -1,278 +1,3007 @@
 use std::{
-    collections::BTreeMap,
-    io::Read,
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{BufRead, Read},
     path::{Path, PathBuf},
 };
 
-use error::{InvalidServerEndpointScheme, WsConnectionInitError};
+use error::{
+    ConnectTimeout, DuplicateFragment, EmptyResponse, HeaderCommandFailed, ImportCycle,
+    InvalidFrontMatter, InvalidServerEndpointScheme, InvalidVariablesFile, InvalidVariablesLine,
+    NoWsEndpoint, OperationNameRequired, PingFailed, RequestTimeout, ResponseTooLarge,
+    RetryableGraphQlError, UnexpectedOperationKind, UnresolvedResponseReference,
+    UploadRequiresPost, WsClosedByServer, WsConnectionInitError,
+};
 use futures_util::{SinkExt, StreamExt};
-use reqwest::header::{HeaderMap, HeaderValue};
+
+use crate::{
+    assertions::select,
+    file_upload::{build_multipart_body, FileUploadDir},
+    lifecycle::{print_lifecycle_event, LifecycleEvent},
+    masking::Mask,
+    metrics::Metrics,
+    operations::{
+        find_operation_kind, infer_operation_name, parse_operation_headers, OperationKind,
+    },
+    request_signing::HmacSigner,
+    tls::rustls_client_config,
+    trace_context::TraceContext,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde_json::json;
-use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use tokio_tungstenite::tungstenite::{
+    client::IntoClientRequest,
+    error::CapacityError,
+    protocol::{frame::coding::CloseCode, WebSocketConfig},
+    Message,
+};
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+use tracing::Instrument;
 use uuid::Uuid;
 
+#[derive(Debug, Clone, Default)]
+pub enum HttpMethod {
+    #[default]
+    Post,
+    Get,
+    // the body is sent as json like with post, the spec does not define other methods
+    Custom(reqwest::Method),
+}
+
+impl std::str::FromStr for HttpMethod {
+    type Err = http::method::InvalidMethod;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_uppercase().as_str() {
+            "POST" => Ok(HttpMethod::Post),
+            "GET" => Ok(HttpMethod::Get),
+            method => Ok(HttpMethod::Custom(reqwest::Method::from_bytes(
+                method.as_bytes(),
+            )?)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RequestEncoding {
+    #[default]
+    Json,
+    // the fields of the json body with the non-string values json stringified
+    Form,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HttpVersion {
+    #[value(name = "1.1")]
+    Http1,
+    // without tls there is no alpn, so the server has to accept h2c connections
+    #[value(name = "2")]
+    Http2,
+    #[default]
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum VariablesPrecedence {
+    #[default]
+    CliWins,
+    FileWins,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VariablesMerge {
+    pub precedence: VariablesPrecedence,
+    pub deep_merge: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum WsProtocol {
+    #[default]
+    GraphqlTransportWs,
+    GraphqlWs,
+    Auto,
+}
+
+impl WsProtocol {
+    fn sec_websocket_protocol(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlTransportWs => "graphql-transport-ws",
+            WsProtocol::GraphqlWs => "graphql-ws",
+            // tungstenite splits the offered subprotocols on commas without trimming
+            WsProtocol::Auto => "graphql-transport-ws,graphql-ws",
+        }
+    }
+
+    fn subscribe_message_type(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlWs => "start",
+            WsProtocol::GraphqlTransportWs | WsProtocol::Auto => "subscribe",
+        }
+    }
+
+    fn complete_message_type(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlWs => "stop",
+            WsProtocol::GraphqlTransportWs | WsProtocol::Auto => "complete",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResumeCursor {
+    pub json_path: String,
+    pub variable: String,
+    pub cursor_file: Option<PathBuf>,
+}
+
+impl ResumeCursor {
+    // the cursor saved by a previous run, nothing if the file does not exist yet
+    pub fn load_saved_cursor(
+        &self,
+    ) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+        let Some(cursor_file) = self.cursor_file.as_ref() else {
+            return Ok(None);
+        };
+
+        match std::fs::read_to_string(cursor_file) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderCommand {
+    pub name: HeaderName,
+    pub value_prefix: Option<String>,
+    pub command: String,
+}
+
+impl HeaderCommand {
+    // the output of the command is never logged, it usually contains a token
+    fn run(&self) -> Result<HeaderValue, HeaderCommandFailed> {
+        let failed = |reason: String| HeaderCommandFailed {
+            name: self.name.to_string(),
+            reason,
+        };
+
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stderr(std::process::Stdio::inherit())
+            .output()
+            .map_err(|e| failed(e.to_string()))?;
+
+        if !output.status.success() {
+            return Err(failed(output.status.to_string()));
+        }
+
+        let output = String::from_utf8(output.stdout)
+            .map_err(|_| failed("the output is not valid utf-8".into()))?;
+        let value = match self.value_prefix.as_ref() {
+            Some(value_prefix) => format!("{value_prefix} {}", output.trim()),
+            None => output.trim().to_string(),
+        };
+
+        let mut value = HeaderValue::from_str(&value)
+            .map_err(|_| failed("the output is not a valid header value".into()))?;
+        value.set_sensitive(true);
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DeadlineFormat {
+    #[default]
+    Rfc3339,
+    UnixMillis,
+}
+
+// the absolute deadline sent to the server, it is counted from the start of every attempt
+#[derive(Debug, Clone)]
+pub struct Deadline {
+    pub duration: std::time::Duration,
+    pub header: HeaderName,
+    pub format: DeadlineFormat,
+}
+
+impl Deadline {
+    fn header_value(&self) -> Result<HeaderValue, reqwest::header::InvalidHeaderValue> {
+        let deadline = std::time::SystemTime::now() + self.duration;
+        let value = match self.format {
+            DeadlineFormat::Rfc3339 => humantime::format_rfc3339_millis(deadline).to_string(),
+            DeadlineFormat::UnixMillis => deadline
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+                .to_string(),
+        };
+
+        HeaderValue::from_str(&value)
+    }
+}
+
+// the headers that are evaluated again before every attempt
+fn attempt_headers(
+    headers: &HeaderMap,
+    options: &ClientOptions,
+) -> Result<HeaderMap, Box<dyn std::error::Error>> {
+    let mut headers = headers.clone();
+    for header_command in options.header_commands.iter() {
+        headers.insert(header_command.name.clone(), header_command.run()?);
+    }
+    if let Some(trace_context) = options.trace_context.as_ref() {
+        trace_context.insert_headers(&mut headers);
+    }
+    if let Some(deadline) = options.deadline.as_ref() {
+        headers.insert(deadline.header.clone(), deadline.header_value()?);
+    }
+
+    Ok(headers)
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseEnvelopeKeys {
+    pub data: String,
+    pub errors: String,
+}
+
+impl Default for ResponseEnvelopeKeys {
+    fn default() -> Self {
+        Self {
+            data: "data".into(),
+            errors: "errors".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    pub try_reconnect_duration: Option<std::time::Duration>,
+    pub omit_operation_name: bool,
+    pub resolve_imports: bool,
+    pub http_method: HttpMethod,
+    pub request_encoding: RequestEncoding,
+    pub http_version: HttpVersion,
+    pub mask: Mask,
+    pub persisted_query_id: Option<String>,
+    pub extensions: Option<serde_json::Map<String, serde_json::Value>>,
+    pub ws_protocol: WsProtocol,
+    pub response_envelope_keys: ResponseEnvelopeKeys,
+    pub file_upload_dirs: Vec<FileUploadDir>,
+    pub auto_transport: bool,
+    pub ws_endpoint: Option<String>,
+    pub connect_timeout: Option<std::time::Duration>,
+    pub request_timeout: Option<std::time::Duration>,
+    pub deadline: Option<Deadline>,
+    pub max_response_size: Option<usize>,
+    pub error_max_len: Option<usize>,
+    pub resume_cursor: Option<ResumeCursor>,
+    pub ws_cookies: Vec<(String, String)>,
+    pub ca_certificates: Vec<String>,
+    pub cache_ttl: Option<std::time::Duration>,
+    pub request_jitter: Option<std::time::Duration>,
+    pub sort_variables: bool,
+    pub print_handshake: bool,
+    pub expected_operation_kind: Option<OperationKind>,
+    pub require_operation_name: bool,
+    pub fail_fast: bool,
+    pub no_ws_compression: bool,
+    pub no_decompress: bool,
+    pub no_conditional: bool,
+    pub emit_lifecycle: bool,
+    pub show_size: bool,
+    pub ws_passthrough_types: Vec<String>,
+    pub retry_on_graphql_error: Vec<String>,
+    pub retry_on_graphql_error_max: u32,
+    pub retry_on_graphql_error_backoff: std::time::Duration,
+    pub strict: bool,
+    pub ws_subscription_id: Option<String>,
+    pub metrics: Option<std::sync::Arc<Metrics>>,
+    pub header_commands: Vec<HeaderCommand>,
+    pub trace_context: Option<TraceContext>,
+    pub hmac_signer: Option<HmacSigner>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResponseMetadata {
+    pub operation_name: Option<String>,
+    pub subscription_id: Option<String>,
+    // the index of the variable set of a subscription event
+    pub variable_set_index: Option<usize>,
+    pub variables: serde_json::Map<String, serde_json::Value>,
+    pub status: Option<reqwest::StatusCode>,
+    pub elapsed: Option<std::time::Duration>,
+    pub etag: Option<HeaderValue>,
+    pub ws_message_type: Option<String>,
+    // the body of a response that is not decompressed with --no-decompress, the response is empty then
+    pub raw_body: Option<Vec<u8>>,
+}
+
 pub async fn execute(
     server_endpoint: impl AsRef<str>,
     headers: HeaderMap,
-    query_path: impl AsRef<Path>,
+    query_path: Option<impl AsRef<Path>>,
     operation_name: Option<impl AsRef<str>>,
-    variables: serde_json::Map<String, serde_json::Value>,
-    response_processor: impl FnMut(GraphQlResponse) -> Result<(), Box<dyn std::error::Error>>,
-    try_reconnect_duration: Option<std::time::Duration>,
+    variable_sets: Vec<serde_json::Map<String, serde_json::Value>>,
+    response_processor: impl FnMut(
+        GraphQlResponse,
+        &ResponseMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+    options: &ClientOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let query = load_query(query_path)?;
+    let server_endpoint = normalize_server_endpoint(server_endpoint.as_ref())?;
 
-    if server_endpoint.as_ref().starts_with("http://")
-        || server_endpoint.as_ref().starts_with("https://")
-    {
+    let query = match query_path {
+        Some(query_path) if options.resolve_imports => Some(load_query_with_imports(query_path)?),
+        Some(query_path) => Some(load_query(query_path)?),
+        None => None,
+    };
+
+    execute_query(
+        &server_endpoint,
+        headers,
+        query,
+        operation_name,
+        variable_sets,
+        response_processor,
+        options,
+    )
+    .await
+}
+
+// the server endpoint is expected to be normalized with normalize_server_endpoint already
+pub async fn execute_query(
+    server_endpoint: &str,
+    headers: HeaderMap,
+    query: Option<String>,
+    operation_name: Option<impl AsRef<str>>,
+    variable_sets: Vec<serde_json::Map<String, serde_json::Value>>,
+    mut response_processor: impl FnMut(
+        GraphQlResponse,
+        &ResponseMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+    options: &ClientOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let resolved_operation_name = operation_name
+        .as_ref()
+        .map(|operation_name| operation_name.as_ref().to_string())
+        .or_else(|| query.as_deref().and_then(infer_operation_name));
+    let response_processor = |response, metadata: &ResponseMetadata| {
+        response_processor(
+            response,
+            &ResponseMetadata {
+                operation_name: resolved_operation_name.clone(),
+                ..metadata.clone()
+            },
+        )
+    };
+
+    let operation_kind = || {
+        query
+            .as_deref()
+            .and_then(|query| find_operation_kind(query, resolved_operation_name.as_deref()))
+    };
+
+    if let Some(expected) = options.expected_operation_kind {
+        let actual = operation_kind();
+        if actual != Some(expected) {
+            return Err(UnexpectedOperationKind { expected, actual }.into());
+        }
+    }
+
+    // a query that cannot be parsed is left for the server to report
+    if options.require_operation_name && operation_name.is_none() {
+        let has_anonymous_operation = query
+            .as_deref()
+            .and_then(|query| parse_operation_headers(query).ok())
+            .is_some_and(|headers| headers.iter().any(|header| header.name.is_none()));
+        if has_anonymous_operation {
+            return Err(OperationNameRequired {
+                reason: "the query has an anonymous operation and no --operation-name is given, \
+                    with --require-operation-name every request has to name its operation"
+                    .into(),
+            }
+            .into());
+        }
+    }
+
+    // a separate websocket endpoint also routes the subscriptions without --auto-transport
+    let is_ws_subscription = (options.auto_transport || options.ws_endpoint.is_some())
+        && operation_kind() == Some(OperationKind::Subscription);
+    let server_endpoint = if is_ws_subscription {
+        let ws_endpoint = options
+            .ws_endpoint
+            .clone()
+            .unwrap_or_else(|| derive_ws_endpoint(server_endpoint));
+
+        if !ws_endpoint.starts_with("ws://") && !ws_endpoint.starts_with("wss://") {
+            return Err(NoWsEndpoint {
+                endpoint: ws_endpoint,
+            }
+            .into());
+        }
+
+        ws_endpoint
+    } else {
+        server_endpoint.to_string()
+    };
+
+    if server_endpoint.starts_with("http://") || server_endpoint.starts_with("https://") {
         http_request(
             server_endpoint,
             headers,
             query,
             operation_name,
-            variables,
+            variable_sets,
             response_processor,
-            try_reconnect_duration,
+            options,
         )
         .await
-    } else if server_endpoint.as_ref().starts_with("ws://")
-        || server_endpoint.as_ref().starts_with("wss://")
-    {
+    } else if server_endpoint.starts_with("ws://") || server_endpoint.starts_with("wss://") {
         ws_request(
             server_endpoint,
             headers,
             query,
             operation_name,
-            variables,
+            variable_sets,
             response_processor,
-            try_reconnect_duration,
+            options,
         )
         .await
     } else {
-        Err(InvalidServerEndpointScheme.into())
+        Err(InvalidServerEndpointScheme {
+            endpoint: server_endpoint,
+        }
+        .into())
+    }
+}
+
+const SERVER_ENDPOINT_SCHEMES: [&str; 4] = ["http", "https", "ws", "wss"];
+
+pub fn normalize_server_endpoint(
+    server_endpoint: &str,
+) -> Result<String, InvalidServerEndpointScheme> {
+    match server_endpoint.split_once("://") {
+        Some((scheme, _rest)) if SERVER_ENDPOINT_SCHEMES.contains(&scheme) => {
+            Ok(server_endpoint.to_string())
+        }
+        Some(_) => Err(InvalidServerEndpointScheme {
+            endpoint: server_endpoint.to_string(),
+        }),
+        None => {
+            tracing::warn!(
+                endpoint = server_endpoint,
+                "the server endpoint has no scheme, using https"
+            );
+            Ok(format!("https://{server_endpoint}"))
+        }
+    }
+}
+
+fn derive_ws_endpoint(server_endpoint: &str) -> String {
+    if let Some(rest) = server_endpoint.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else if let Some(rest) = server_endpoint.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else {
+        server_endpoint.to_string()
     }
 }
 
 pub fn load_query(query_path: impl AsRef<Path>) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::open(query_path.as_ref())?;
+    let (query, _front_matter) = read_query_file(query_path.as_ref())?;
+
+    Ok(query)
+}
+
+// the file is read once, the front-matter of the query file provides default variables
+pub fn load_query_with_front_matter(
+    query_path: impl AsRef<Path>,
+) -> Result<(String, serde_json::Map<String, serde_json::Value>), Box<dyn std::error::Error>> {
+    let query_path = query_path.as_ref();
+    let (query, front_matter) = read_query_file(query_path)?;
+    let Some(front_matter) = front_matter else {
+        return Ok((query, serde_json::Map::default()));
+    };
+
+    // yaml is a superset of json, so a json object is accepted as well
+    match serde_yaml::from_str(&front_matter) {
+        Ok(serde_json::Value::Object(default_variables)) => Ok((query, default_variables)),
+        Ok(_) => Err(InvalidFrontMatter {
+            path: query_path.to_path_buf(),
+            message: "expected an object between the '---' lines".to_string(),
+        }
+        .into()),
+        Err(e) => Err(InvalidFrontMatter {
+            path: query_path.to_path_buf(),
+            message: e.to_string(),
+        }
+        .into()),
+    }
+}
+
+// returns the query without the front-matter and the front-matter itself
+fn read_query_file(
+    query_path: &Path,
+) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+    let mut file = std::fs::File::open(query_path)?;
     let mut query = String::new();
     file.read_to_string(&mut query)?;
 
-    Ok(query)
+    match split_front_matter(&query) {
+        // the front-matter is replaced with empty lines to keep the positions of the errors
+        Some((front_matter_end, front_matter)) => Ok((
+            format!(
+                "{}{}",
+                "\n".repeat(query[..front_matter_end].matches('\n').count()),
+                &query[front_matter_end..],
+            ),
+            Some(front_matter.to_string()),
+        )),
+        None => Ok((query, None)),
+    }
 }
 
-pub fn load_variables(
-    variables_from_json: Option<PathBuf>,
-    variables_list: Vec<(String, serde_json::Value)>,
-) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn std::error::Error>> {
-    let mut variables = if let Some(json_path) = variables_from_json {
-        let mut file = std::fs::File::open(json_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+const FRONT_MATTER_DELIMITER: &str = "---";
 
-        serde_json::from_str(&contents)?
-    } else {
-        serde_json::Map::default()
-    };
+// returns the end of the front-matter block and its contents between the delimiter lines
+fn split_front_matter(document: &str) -> Option<(usize, &str)> {
+    let mut lines = document.split_inclusive('\n');
+    let first_line = lines.next()?;
+    if first_line.trim_end() != FRONT_MATTER_DELIMITER {
+        return None;
+    }
 
-    variables.extend(
-        variables_list
-            .into_iter()
-            .map(|(name, value)| (name.to_string(), value)),
-    );
+    let mut offset = first_line.len();
+    for line in lines {
+        if line.trim_end() == FRONT_MATTER_DELIMITER {
+            return Some((offset + line.len(), &document[first_line.len()..offset]));
+        }
+        offset += line.len();
+    }
 
-    Ok(variables)
+    None
 }
 
-pub async fn try_http_request(
-    server_endpoint: impl AsRef<str>,
-    headers: HeaderMap,
+pub fn load_query_with_imports(
+    query_path: impl AsRef<Path>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let query = load_query(query_path.as_ref())?;
+
+    resolve_imports(query_path, query)
+}
+
+// resolves the imports of a query that is already loaded from the given path
+pub fn resolve_imports(
+    query_path: impl AsRef<Path>,
     query: String,
-    operation_name: Option<impl AsRef<str>>,
-    variables: serde_json::Map<String, serde_json::Value>,
-    response_processor: &mut impl FnMut(GraphQlResponse) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let query_path = query_path.as_ref().canonicalize()?;
+    let mut import_stack = Vec::new();
+    let mut imported_paths = HashSet::from([query_path.clone()]);
+    let mut documents = Vec::new();
+
+    collect_document_imports(
+        query_path,
+        query,
+        &mut import_stack,
+        &mut imported_paths,
+        &mut documents,
+    )?;
+
+    Ok(documents.join("\n"))
+}
+
+fn collect_imports(
+    path: &Path,
+    import_stack: &mut Vec<PathBuf>,
+    imported_paths: &mut HashSet<PathBuf>,
+    documents: &mut Vec<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::ClientBuilder::new().build()?;
-
-    let response = client
-        .post(server_endpoint.as_ref())
-        .headers(headers)
-        .json(&json!({
-            "operationName": operation_name.as_ref().map(|s| s.as_ref()),
-            "query": query,
-            "variables": variables,
-        }))
-        .send()
-        .await?;
+    let path = path.canonicalize()?;
+
+    if import_stack.contains(&path) {
+        import_stack.push(path);
+        return Err(ImportCycle {
+            paths: std::mem::take(import_stack),
+        }
+        .into());
+    }
+
+    if !imported_paths.insert(path.clone()) {
+        // the file is already part of the document
+        return Ok(());
+    }
 
-    let response = response.json::<GraphQlResponse>().await?;
+    let document = load_query(&path)?;
+    collect_document_imports(path, document, import_stack, imported_paths, documents)
+}
+
+fn collect_document_imports(
+    path: PathBuf,
+    document: String,
+    import_stack: &mut Vec<PathBuf>,
+    imported_paths: &mut HashSet<PathBuf>,
+    documents: &mut Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let imports = document
+        .lines()
+        .filter_map(parse_import_line)
+        .map(|import| base_dir.join(import))
+        .collect::<Vec<_>>();
+
+    documents.push(document);
 
-    response_processor(response)?;
+    import_stack.push(path);
+    for import in imports {
+        collect_imports(&import, import_stack, imported_paths, documents)?;
+    }
+    import_stack.pop();
 
     Ok(())
 }
 
-pub async fn http_request(
-    server_endpoint: impl AsRef<str>,
-    mut headers: HeaderMap,
+// the duplicate check is left to the parser, a fragments file alone is not a valid query
+pub fn append_fragment_files(
     query: String,
-    operation_name: Option<impl AsRef<str>>,
-    variables: serde_json::Map<String, serde_json::Value>,
-    mut response_processor: impl FnMut(GraphQlResponse) -> Result<(), Box<dyn std::error::Error>>,
-    try_reconnect_duration: Option<std::time::Duration>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    headers
-        .entry("accept")
-        .or_insert_with(|| HeaderValue::from_static("application/json"));
+    fragment_paths: &[PathBuf],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut query = query;
+    for fragment_path in fragment_paths {
+        let appended = format!("{}\n\n{}", query.trim_end(), load_query(fragment_path)?);
 
-    loop {
-        if let Err(e) = try_http_request(
-            server_endpoint.as_ref(),
-            headers.clone(),
-            query.clone(),
-            operation_name.as_ref().map(|s| s.as_ref()),
-            variables.clone(),
-            &mut response_processor,
-        )
-        .await
+        if let Err(async_graphql_parser::Error::FragmentDuplicated { fragment, .. }) =
+            async_graphql_parser::parse_query(&appended)
         {
-            log::error!("{:?}", e);
+            return Err(DuplicateFragment {
+                name: fragment.to_string(),
+                path: fragment_path.clone(),
+            }
+            .into());
         }
 
-        if let Some(duration) = try_reconnect_duration {
-            tokio::time::sleep(duration).await;
-        } else {
-            break Ok(());
-        }
+        query = appended;
     }
-}
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct GraphQlResponse {
-    pub data: Option<serde_json::Value>,
-    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
-    pub extensions: BTreeMap<String, serde_json::Value>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub errors: Vec<serde_json::Map<String, serde_json::Value>>,
+    Ok(query)
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct WsResponse {
-    #[allow(unused)]
-    r#type: String,
-    #[allow(unused)]
-    id: String,
-    payload: Option<GraphQlResponse>,
+fn parse_import_line(line: &str) -> Option<&str> {
+    let import = line
+        .trim()
+        .strip_prefix('#')?
+        .trim_start()
+        .strip_prefix("import")?
+        .trim();
+
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| import.strip_prefix(quote)?.strip_suffix(quote))
 }
 
-async fn try_ws_request(
-    server_endpoint: impl AsRef<str>,
-    headers: HeaderMap,
-    query: String,
-    operation_name: Option<impl AsRef<str>>,
-    variables: serde_json::Map<String, serde_json::Value>,
-    response_processor: &mut impl FnMut(GraphQlResponse) -> Result<(), Box<dyn std::error::Error>>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut request = server_endpoint.as_ref().into_client_request()?;
+pub fn load_variables(
+    variables_from_json: Vec<PathBuf>,
+    variables_list: Vec<(String, serde_json::Value)>,
+    merge: VariablesMerge,
+) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn std::error::Error>> {
+    let mut variables = serde_json::Map::default();
+    for json_path in variables_from_json {
+        merge_variable_file(
+            &mut variables,
+            serde_json::from_value(read_variables_file(&json_path)?)?,
+            merge,
+        );
+    }
 
-    request.headers_mut().extend(headers);
-    request.headers_mut().insert(
-        "sec-websocket-protocol",
-        HeaderValue::from_str("graphql-transport-ws")?,
-    );
+    merge_variables(&mut variables, &variables_list, merge);
 
-    request.headers_mut().insert(
-        "sec-websocket-extensions",
-        HeaderValue::from_str("permessage-deflate; client_max_window_bits")?,
-    );
+    Ok(variables)
+}
 
-    request.extensions_mut().insert("permessage-deflate");
-    request.extensions_mut().insert("client_max_window_bits");
+// the files are merged in order, an array of objects multiplies the variable sets
+pub fn load_variable_sets(
+    variables_from_json: Vec<PathBuf>,
+    variables_list: Vec<(String, serde_json::Value)>,
+    merge: VariablesMerge,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, Box<dyn std::error::Error>> {
+    let mut variable_sets = vec![serde_json::Map::default()];
 
-    let (mut ws_stream, mut _server_response) = tokio_tungstenite::connect_async(request).await?;
+    for json_path in variables_from_json {
+        let file_variable_sets = match read_variables_file(&json_path)? {
+            serde_json::Value::Object(variables) => vec![variables],
+            // an empty array would silently send no request at all
+            serde_json::Value::Array(items) if items.is_empty() => {
+                return Err(InvalidVariablesFile { path: json_path }.into())
+            }
+            serde_json::Value::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    serde_json::Value::Object(variables) => Ok(variables),
+                    _ => Err(InvalidVariablesFile {
+                        path: json_path.clone(),
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(InvalidVariablesFile { path: json_path }.into()),
+        };
 
-    ws_stream
-        .send(Message::text(serde_json::to_string(&json!({
-            "type": "connection_init",
-            "payload": {}
-        }))?))
-        .await?;
+        variable_sets = variable_sets
+            .iter()
+            .flat_map(|variables| {
+                file_variable_sets.iter().map(|file_variables| {
+                    let mut variables = variables.clone();
+                    merge_variable_file(&mut variables, file_variables.clone(), merge);
+                    variables
+                })
+            })
+            .collect();
+    }
 
-    ws_stream.next().await.ok_or(WsConnectionInitError)??;
+    for variables in variable_sets.iter_mut() {
+        merge_variables(variables, &variables_list, merge);
+    }
 
-    ws_stream
-        .send(Message::text(serde_json::to_string(&json!({
-            "id": Uuid::new_v4().to_string(),
-            "type": "subscribe",
-            "payload": {
-                "operationName": operation_name.as_ref().map(|s| s.as_ref()),
-                "query": query,
-                "variables": variables,
-            }
-        }))?))
-        .await?;
+    Ok(variable_sets)
+}
+
+pub fn load_variable_sets_ndjson(
+    ndjson_path: impl AsRef<Path>,
+    variables_list: Vec<(String, serde_json::Value)>,
+    merge: VariablesMerge,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, Box<dyn std::error::Error>> {
+    let ndjson_path = ndjson_path.as_ref();
+    let reader: Box<dyn BufRead> = if ndjson_path == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(std::io::BufReader::new(std::fs::File::open(ndjson_path)?))
+    };
 
-    while let Some(message) = ws_stream.next().await {
-        match message {
-            Ok(message) => {
-                if let Ok(message) = message.into_text() {
-                    let response = serde_json::from_str::<WsResponse>(&message)?;
+    let mut variable_sets = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
 
-                    if let Some(payload) = response.payload {
-                        response_processor(payload)?;
-                    } else if response.r#type == "complete" {
-                        break;
-                    }
-                } else {
-                    log::error!("Invalid message received from websocket");
-                }
+        match serde_json::from_str(&line) {
+            Ok(serde_json::Value::Object(mut variables)) => {
+                merge_variables(&mut variables, &variables_list, merge);
+                variable_sets.push(variables);
             }
-            Err(e) => {
-                log::error!("{e}");
+            _ => {
+                return Err(InvalidVariablesLine {
+                    path: ndjson_path.to_path_buf(),
+                    line: index + 1,
+                }
+                .into())
             }
         }
     }
 
-    Ok(())
+    Ok(variable_sets)
 }
 
-pub async fn ws_request(
-    server_endpoint: impl AsRef<str>,
-    headers: HeaderMap,
-    query: String,
-    operation_name: Option<impl AsRef<str>>,
-    variables: serde_json::Map<String, serde_json::Value>,
-    mut response_processor: impl FnMut(GraphQlResponse) -> Result<(), Box<dyn std::error::Error>>,
-    try_reconnect_duration: Option<std::time::Duration>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    loop {
-        if let Err(e) = try_ws_request(
-            server_endpoint.as_ref(),
-            headers.clone(),
-            query.clone(),
-            operation_name.as_ref().map(|s| s.as_ref()),
-            variables.clone(),
-            &mut response_processor,
-        )
-        .await
-        {
-            log::error!("{:?}", e);
-        }
+// yaml files are converted to json, any other file is parsed as json
+fn read_variables_file(path: &Path) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut file = std::fs::File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
 
-        if let Some(duration) = try_reconnect_duration {
-            tokio::time::sleep(duration).await;
-        } else {
-            break Ok(());
-        }
+    let is_yaml = path
+        .extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml");
+    if is_yaml {
+        Ok(serde_yaml::from_str(&contents)?)
+    } else {
+        Ok(serde_json::from_str(&contents)?)
     }
 }
 
-pub mod error {
-    #[derive(Debug, thiserror::Error)]
-    #[error("WsConnectionInitError")]
-    pub struct WsConnectionInitError;
+// a later file always overrides the earlier ones
+fn merge_variable_file(
+    variables: &mut serde_json::Map<String, serde_json::Value>,
+    file_variables: serde_json::Map<String, serde_json::Value>,
+    merge: VariablesMerge,
+) {
+    for (name, value) in file_variables {
+        match variables.entry(name) {
+            serde_json::map::Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+            serde_json::map::Entry::Occupied(mut entry) => {
+                if merge.deep_merge {
+                    merge_json_values(entry.get_mut(), value, true);
+                } else {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+}
+
+fn merge_variables(
+    variables: &mut serde_json::Map<String, serde_json::Value>,
+    variables_list: &[(String, serde_json::Value)],
+    merge: VariablesMerge,
+) {
+    let cli_wins = merge.precedence == VariablesPrecedence::CliWins;
+
+    for (name, value) in variables_list.iter().cloned() {
+        match variables.entry(name) {
+            serde_json::map::Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+            serde_json::map::Entry::Occupied(mut entry) => {
+                if merge.deep_merge {
+                    merge_json_values(entry.get_mut(), value, cli_wins);
+                } else if cli_wins {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+}
+
+// objects are merged key by key, any other pair of values is resolved by the precedence
+fn merge_json_values(target: &mut serde_json::Value, value: serde_json::Value, value_wins: bool) {
+    match (target, value) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(value)) => {
+            for (key, value) in value {
+                match target.entry(key) {
+                    serde_json::map::Entry::Vacant(entry) => {
+                        entry.insert(value);
+                    }
+                    serde_json::map::Entry::Occupied(mut entry) => {
+                        merge_json_values(entry.get_mut(), value, value_wins);
+                    }
+                }
+            }
+        }
+        (target, value) => {
+            if value_wins {
+                *target = value;
+            }
+        }
+    }
+}
+
+const RESPONSE_REFERENCE_PREFIX: &str = "@response:";
+
+pub fn resolve_response_references(
+    variables: &mut serde_json::Map<String, serde_json::Value>,
+    response_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut response = None;
+
+    for value in variables.values_mut() {
+        let Some(json_path) = value
+            .as_str()
+            .and_then(|value| value.strip_prefix(RESPONSE_REFERENCE_PREFIX))
+        else {
+            continue;
+        };
+
+        let response = match response.as_ref() {
+            Some(response) => response,
+            None => {
+                let response_path = response_path.ok_or_else(|| UnresolvedResponseReference {
+                    json_path: json_path.to_string(),
+                })?;
+                response.insert(serde_json::from_str::<serde_json::Value>(
+                    &std::fs::read_to_string(response_path)?,
+                )?)
+            }
+        };
+
+        *value = select(response, json_path)
+            .ok_or_else(|| UnresolvedResponseReference {
+                json_path: json_path.to_string(),
+            })?
+            .clone();
+    }
+
+    Ok(())
+}
+
+pub fn build_request_body(
+    query: Option<&str>,
+    operation_name: Option<&str>,
+    variables: &serde_json::Map<String, serde_json::Value>,
+    options: &ClientOptions,
+) -> serde_json::Value {
+    let mut body = serde_json::Map::new();
+
+    if !options.omit_operation_name {
+        body.insert("operationName".into(), json!(operation_name));
+    }
+    if let Some(query) = query {
+        body.insert("query".into(), json!(query));
+    }
+    if options.sort_variables {
+        body.insert("variables".into(), sort_json_keys(json!(variables)));
+    } else {
+        body.insert("variables".into(), json!(variables));
+    }
+
+    let mut extensions = options.extensions.clone().unwrap_or_default();
+    if let Some(persisted_query_id) = options.persisted_query_id.as_ref() {
+        extensions.insert(
+            "persistedQuery".into(),
+            json!({
+                "version": 1,
+                "sha256Hash": persisted_query_id,
+            }),
+        );
+    }
+    if !extensions.is_empty() {
+        body.insert("extensions".into(), serde_json::Value::Object(extensions));
+    }
+
+    serde_json::Value::Object(body)
+}
+
+pub fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries = object.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_json_keys(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(array) => {
+            serde_json::Value::Array(array.into_iter().map(sort_json_keys).collect())
+        }
+        value => value,
+    }
+}
+
+fn request_body_to_query_parameters(body: &serde_json::Value) -> Vec<(String, String)> {
+    body.as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_key, value)| !value.is_null())
+        .map(|(key, value)| match value {
+            serde_json::Value::String(value) => (key.clone(), value.clone()),
+            value => (key.clone(), value.to_string()),
+        })
+        .collect()
+}
+
+pub fn encode_request_body(
+    body: &serde_json::Value,
+    options: &ClientOptions,
+) -> Result<(&'static str, Vec<u8>), Box<dyn std::error::Error>> {
+    match options.request_encoding {
+        RequestEncoding::Json => Ok(("application/json", serde_json::to_vec(body)?)),
+        RequestEncoding::Form => Ok((
+            "application/x-www-form-urlencoded",
+            serde_urlencoded::to_string(request_body_to_query_parameters(body))?.into_bytes(),
+        )),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn try_http_request(
+    client: &reqwest::Client,
+    server_endpoint: impl AsRef<str>,
+    mut headers: HeaderMap,
+    query: Option<String>,
+    operation_name: Option<impl AsRef<str>>,
+    variables: serde_json::Map<String, serde_json::Value>,
+    response_processor: &mut impl FnMut(
+        GraphQlResponse,
+        &ResponseMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+    options: &ClientOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let started_at = std::time::Instant::now();
+
+    let body = build_request_body(
+        query.as_deref(),
+        operation_name.as_ref().map(|s| s.as_ref()),
+        &variables,
+        options,
+    );
+
+    // the body is serialized once, so a signature covers exactly the bytes that are sent
+    let (request, sent_body) = match &options.http_method {
+        HttpMethod::Post if !options.file_upload_dirs.is_empty() => {
+            let multipart = build_multipart_body(body, &options.file_upload_dirs)?;
+            (
+                client
+                    .post(server_endpoint.as_ref())
+                    .header(reqwest::header::CONTENT_TYPE, multipart.content_type),
+                multipart.body,
+            )
+        }
+        HttpMethod::Post => {
+            let (content_type, encoded_body) = encode_request_body(&body, options)?;
+            (
+                client
+                    .post(server_endpoint.as_ref())
+                    .header(reqwest::header::CONTENT_TYPE, content_type),
+                encoded_body,
+            )
+        }
+        HttpMethod::Get if !options.file_upload_dirs.is_empty() => {
+            return Err(UploadRequiresPost.into())
+        }
+        HttpMethod::Get => (
+            client
+                .get(server_endpoint.as_ref())
+                .query(&request_body_to_query_parameters(&body)),
+            Vec::new(),
+        ),
+        HttpMethod::Custom(_method) if !options.file_upload_dirs.is_empty() => {
+            return Err(UploadRequiresPost.into())
+        }
+        HttpMethod::Custom(method) => {
+            let (content_type, encoded_body) = encode_request_body(&body, options)?;
+            (
+                client
+                    .request(method.clone(), server_endpoint.as_ref())
+                    .header(reqwest::header::CONTENT_TYPE, content_type),
+                encoded_body,
+            )
+        }
+    };
+
+    if let Some(hmac_signer) = options.hmac_signer.as_ref() {
+        headers.insert(hmac_signer.header.clone(), hmac_signer.sign(&sent_body));
+    }
+    let request = if sent_body.is_empty() {
+        request
+    } else {
+        request.body(sent_body)
+    };
+
+    if let Some(metrics) = options.metrics.as_ref() {
+        metrics.record_request_sent();
+    }
+    tracing::debug!(
+        method = ?options.http_method,
+        endpoint = server_endpoint.as_ref(),
+        headers = %options.mask.headers(&headers),
+        variables = %options.mask.variables(&variables),
+        "sending request"
+    );
+
+    let mut response =
+        request
+            .headers(headers)
+            .send()
+            .await
+            .map_err(|e| -> Box<dyn std::error::Error> {
+                match (options.connect_timeout, options.request_timeout) {
+                    (Some(timeout), _) if e.is_connect() && e.is_timeout() => {
+                        ConnectTimeout { timeout }.into()
+                    }
+                    (_, Some(timeout)) if e.is_timeout() => RequestTimeout { timeout }.into(),
+                    _ => e.into(),
+                }
+            })?;
+
+    let status = response.status();
+    // only sent for a conditional request, the previous response is still the current one
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        tracing::info!("not modified, the response is skipped");
+        return Ok(());
+    }
+    let etag = response.headers().get(reqwest::header::ETAG).cloned();
+    let content_encoding = response
+        .headers()
+        .get(reqwest::header::CONTENT_ENCODING)
+        .filter(|content_encoding| *content_encoding != "identity")
+        .cloned();
+    // the decompression removes the content length and encoding headers of an encoded response
+    let content_length = response.content_length();
+    tracing::debug!(
+        status = status.as_u16(),
+        elapsed = ?started_at.elapsed(),
+        "response received"
+    );
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+
+        if let Some(limit) = options
+            .max_response_size
+            .filter(|limit| body.len() > *limit)
+        {
+            return Err(ResponseTooLarge { limit }.into());
+        }
+    }
+
+    if options.show_size {
+        eprintln!(
+            "content length = {}, body size = {} bytes, content encoding = {}",
+            content_length.map_or_else(|| "unknown".into(), |length| format!("{length} bytes")),
+            body.len(),
+            content_encoding
+                .as_ref()
+                .and_then(|content_encoding| content_encoding.to_str().ok())
+                .unwrap_or("identity"),
+        );
+    }
+
+    // without decompression an encoded body cannot be parsed, it is passed on as it is received
+    if let Some(content_encoding) = content_encoding.filter(|_| options.no_decompress) {
+        tracing::warn!(
+            content_encoding = ?content_encoding,
+            "the response is not decompressed"
+        );
+        return response_processor(
+            GraphQlResponse {
+                data: None,
+                extensions: BTreeMap::new(),
+                errors: Vec::new(),
+            },
+            &ResponseMetadata {
+                variables,
+                status: Some(status),
+                elapsed: Some(started_at.elapsed()),
+                etag,
+                raw_body: Some(body.to_vec()),
+                ..Default::default()
+            },
+        );
+    }
+
+    // some gateways answer mutations with 204 or an empty body
+    let response = if status == reqwest::StatusCode::NO_CONTENT || body.is_empty() {
+        if options.strict {
+            return Err(EmptyResponse {
+                status: status.as_u16(),
+            }
+            .into());
+        }
+
+        GraphQlResponse {
+            data: None,
+            extensions: BTreeMap::new(),
+            errors: Vec::new(),
+        }
+    } else {
+        parse_graphql_response(
+            serde_json::from_slice::<serde_json::Value>(&body)?,
+            &options.response_envelope_keys,
+        )?
+    };
+
+    response_processor(
+        response,
+        &ResponseMetadata {
+            variables,
+            status: Some(status),
+            elapsed: Some(started_at.elapsed()),
+            etag,
+            ..Default::default()
+        },
+    )?;
+
+    Ok(())
+}
+
+const PING_QUERY: &str = "{ __typename }";
+
+pub async fn ping(
+    client: &reqwest::Client,
+    server_endpoint: impl AsRef<str>,
+    mut headers: HeaderMap,
+    options: &ClientOptions,
+) -> Result<std::time::Duration, Box<dyn std::error::Error>> {
+    headers
+        .entry("accept")
+        .or_insert_with(|| HeaderValue::from_static("application/json"));
+
+    let mut outcome = None;
+
+    try_http_request(
+        client,
+        server_endpoint,
+        headers,
+        Some(PING_QUERY.to_string()),
+        None::<&str>,
+        serde_json::Map::default(),
+        &mut |response, metadata| {
+            outcome = Some((
+                metadata.status,
+                metadata.elapsed.unwrap_or_default(),
+                response.errors.len(),
+            ));
+            Ok(())
+        },
+        options,
+    )
+    .await?;
+
+    match outcome {
+        Some((Some(status), elapsed, 0)) if status.is_success() => Ok(elapsed),
+        Some((status, _elapsed, errors)) => Err(PingFailed {
+            status: status.map_or(0, |status| status.as_u16()),
+            errors,
+        }
+        .into()),
+        None => Err(PingFailed {
+            status: 0,
+            errors: 0,
+        }
+        .into()),
+    }
+}
+
+pub async fn http_request(
+    server_endpoint: impl AsRef<str>,
+    mut headers: HeaderMap,
+    query: Option<String>,
+    operation_name: Option<impl AsRef<str>>,
+    variable_sets: Vec<serde_json::Map<String, serde_json::Value>>,
+    mut response_processor: impl FnMut(
+        GraphQlResponse,
+        &ResponseMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+    options: &ClientOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    headers
+        .entry("accept")
+        .or_insert_with(|| HeaderValue::from_static("application/json"));
+
+    let mut client_builder = reqwest::ClientBuilder::new();
+    if let Some(connect_timeout) = options.connect_timeout {
+        client_builder = client_builder.connect_timeout(connect_timeout);
+    }
+    if let Some(request_timeout) = options.request_timeout {
+        client_builder = client_builder.timeout(request_timeout);
+    }
+    for ca_certificate in options.ca_certificates.iter() {
+        client_builder = client_builder
+            .add_root_certificate(reqwest::Certificate::from_pem(ca_certificate.as_bytes())?);
+    }
+    if options.no_decompress {
+        client_builder = client_builder.no_gzip().no_brotli().no_deflate();
+    }
+    client_builder = match options.http_version {
+        HttpVersion::Http1 => client_builder.http1_only(),
+        HttpVersion::Http2 => client_builder.http2_prior_knowledge(),
+        HttpVersion::Auto => client_builder,
+    };
+    let client = client_builder.build()?;
+
+    // request body -> time of the last successful response
+    let mut response_cache = HashMap::<String, std::time::Instant>::new();
+    // request body -> etag of the last response, sent back as If-None-Match
+    let mut etags = HashMap::<String, HeaderValue>::new();
+    let conditional = matches!(options.http_method, HttpMethod::Get) && !options.no_conditional;
+
+    // only a failed attempt is followed by a reconnect, the others are the next poll
+    let mut previous_attempt_failed = false;
+    for attempt in 1.. {
+        if previous_attempt_failed {
+            tracing::info!(attempt, "reconnecting");
+            emit_lifecycle_event(
+                options,
+                LifecycleEvent::Reconnecting,
+                json!({ "attempt": attempt }),
+            );
+            if let Some(metrics) = options.metrics.as_ref() {
+                metrics.record_reconnect();
+            }
+        }
+        let mut attempt_failed = false;
+
+        'attempt: {
+            let attempt_headers = match attempt_headers(&headers, options) {
+                Ok(attempt_headers) => attempt_headers,
+                Err(e) => {
+                    if let Some(metrics) = options.metrics.as_ref() {
+                        metrics.record_error();
+                    }
+                    emit_lifecycle_event(
+                        options,
+                        LifecycleEvent::Error,
+                        json!({ "attempt": attempt, "message": e.to_string() }),
+                    );
+                    if options.fail_fast {
+                        return Err(e);
+                    }
+                    log_failed_attempt(attempt, e.as_ref(), options);
+                    attempt_failed = true;
+                    break 'attempt;
+                }
+            };
+
+            for variables in variable_sets.iter() {
+                let request_signature = serde_json::to_string(&build_request_body(
+                    query.as_deref(),
+                    operation_name.as_ref().map(|s| s.as_ref()),
+                    variables,
+                    options,
+                ))?;
+                if let Some(cache_ttl) = options.cache_ttl {
+                    if response_cache
+                        .get(&request_signature)
+                        .is_some_and(|cached_at| cached_at.elapsed() < cache_ttl)
+                    {
+                        tracing::info!("cache hit, the request is skipped");
+                        continue;
+                    }
+                }
+
+                if let Some(request_jitter) = options.request_jitter {
+                    tokio::time::sleep(random_duration(request_jitter)).await;
+                }
+
+                let mut request_headers = attempt_headers.clone();
+                if let Some(etag) = etags.get(&request_signature).filter(|_| conditional) {
+                    request_headers.insert(reqwest::header::IF_NONE_MATCH, etag.clone());
+                }
+
+                emit_lifecycle_event(
+                    options,
+                    LifecycleEvent::Connecting,
+                    json!({ "endpoint": server_endpoint.as_ref(), "attempt": attempt }),
+                );
+
+                let mut etag = None;
+                let mut retries = 0;
+                let result = loop {
+                    let result = try_http_request(
+                        &client,
+                        server_endpoint.as_ref(),
+                        request_headers.clone(),
+                        query.clone(),
+                        operation_name.as_ref().map(|s| s.as_ref()),
+                        variables.clone(),
+                        &mut |response, metadata| {
+                            if let Some(code) = retryable_error_code(&response, options) {
+                                return Err(RetryableGraphQlError { code, retries }.into());
+                            }
+                            etag = metadata.etag.clone();
+                            emit_lifecycle_event(
+                                options,
+                                LifecycleEvent::Event,
+                                json!({ "status": metadata.status.map(|status| status.as_u16()) }),
+                            );
+                            response_processor(response, metadata)
+                        },
+                        options,
+                    )
+                    .instrument(tracing::info_span!("attempt", attempt, retries))
+                    .await;
+
+                    match result {
+                        Err(e)
+                            if e.is::<RetryableGraphQlError>()
+                                && retries < options.retry_on_graphql_error_max =>
+                        {
+                            let backoff =
+                                retry_backoff(options.retry_on_graphql_error_backoff, retries);
+                            tracing::warn!(
+                                error = %e,
+                                retry_in = %humantime::format_duration(backoff),
+                                "retrying the graphql error"
+                            );
+                            tokio::time::sleep(backoff).await;
+                            retries += 1;
+                        }
+                        result => break result,
+                    }
+                };
+
+                match result {
+                    Ok(()) => {
+                        if let Some(etag) = etag.filter(|_| conditional) {
+                            etags.insert(request_signature.clone(), etag);
+                        }
+                        if options.cache_ttl.is_some() {
+                            response_cache.insert(request_signature, std::time::Instant::now());
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(metrics) = options.metrics.as_ref() {
+                            metrics.record_error();
+                        }
+                        emit_lifecycle_event(
+                            options,
+                            LifecycleEvent::Error,
+                            json!({ "attempt": attempt, "message": e.to_string() }),
+                        );
+                        if options.fail_fast {
+                            return Err(e);
+                        }
+                        log_failed_attempt(attempt, e.as_ref(), options);
+                        attempt_failed = true;
+                    }
+                }
+            }
+        }
+
+        previous_attempt_failed = attempt_failed;
+        if let Some(duration) = options.try_reconnect_duration {
+            tokio::time::sleep(duration).await;
+        } else {
+            break;
+        }
+    }
+
+    emit_lifecycle_event(options, LifecycleEvent::Completed, json!({}));
+
+    Ok(())
+}
+
+fn emit_lifecycle_event(
+    options: &ClientOptions,
+    event: LifecycleEvent,
+    details: serde_json::Value,
+) {
+    if options.emit_lifecycle {
+        print_lifecycle_event(event, details);
+    }
+}
+
+// the variant bits of the uuid end up in the least significant byte, so their bias is negligible
+fn random_duration(max_duration: std::time::Duration) -> std::time::Duration {
+    let mut random_bytes = [0u8; 8];
+    random_bytes.copy_from_slice(&Uuid::new_v4().as_bytes()[8..]);
+    let random = u64::from_le_bytes(random_bytes);
+
+    max_duration.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+const MAX_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+// the backoff is doubled after every retry up to MAX_RETRY_BACKOFF
+fn retry_backoff(backoff: std::time::Duration, retries: u32) -> std::time::Duration {
+    let backoff = backoff.as_secs_f64() * 2f64.powf(retries.into());
+
+    std::time::Duration::try_from_secs_f64(backoff)
+        .map_or(MAX_RETRY_BACKOFF, |backoff| backoff.min(MAX_RETRY_BACKOFF))
+}
+
+fn retryable_error_code(response: &GraphQlResponse, options: &ClientOptions) -> Option<String> {
+    response
+        .errors
+        .iter()
+        .filter_map(|error| error.get("extensions")?.get("code")?.as_str())
+        .find(|code| {
+            options
+                .retry_on_graphql_error
+                .iter()
+                .any(|retry_code| retry_code == code)
+        })
+        .map(str::to_string)
+}
+
+fn log_failed_attempt(attempt: usize, error: &dyn std::error::Error, options: &ClientOptions) {
+    let mut error = format!("{error:?}");
+    if let Some((cut_at, _char)) = options
+        .error_max_len
+        .and_then(|error_max_len| error.char_indices().nth(error_max_len))
+    {
+        error.truncate(cut_at);
+        error.push('…');
+    }
+
+    match options.try_reconnect_duration {
+        Some(retry_in) => tracing::error!(
+            attempt,
+            error,
+            retry_in = %humantime::format_duration(retry_in),
+            "attempt failed"
+        ),
+        None => tracing::error!(attempt, error, "attempt failed"),
+    }
+}
+
+fn parse_graphql_response(
+    mut response: serde_json::Value,
+    keys: &ResponseEnvelopeKeys,
+) -> Result<GraphQlResponse, serde_json::Error> {
+    if let Some(response) = response.as_object_mut() {
+        for (key, default_key) in [(&keys.data, "data"), (&keys.errors, "errors")] {
+            if key != default_key {
+                let value = response.remove(key);
+                response.remove(default_key);
+
+                if let Some(value) = value {
+                    response.insert(default_key.into(), value);
+                }
+            }
+        }
+    }
+
+    serde_json::from_value(response)
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct GraphQlResponse {
+    pub data: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct WsResponse {
+    r#type: String,
+    #[serde(default)]
+    id: Option<String>,
+    payload: Option<GraphQlResponse>,
+}
+
+pub enum WsEvent {
+    Next {
+        subscription_id: Option<String>,
+        payload: GraphQlResponse,
+    },
+    Complete {
+        subscription_id: Option<String>,
+    },
+    // a vendor specific frame whose type is listed in the passthrough types
+    Passthrough {
+        message_type: String,
+        message: serde_json::Value,
+    },
+}
+
+// one connection that runs any number of subscribe/complete cycles, the operations are multiplexed by their ids
+pub struct WsSession<'a> {
+    ws_stream: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+    ws_protocol: WsProtocol,
+    options: &'a ClientOptions,
+}
+
+impl<'a> WsSession<'a> {
+    // the connection is initialized, so the session is ready to subscribe when it is returned
+    pub async fn connect(
+        server_endpoint: impl AsRef<str>,
+        headers: &HeaderMap,
+        options: &'a ClientOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut request = server_endpoint.as_ref().into_client_request()?;
+
+        request
+            .headers_mut()
+            .extend(attempt_headers(headers, options)?);
+        if !options.ws_cookies.is_empty() {
+            // the cookies are appended to the cookie header given with the other headers
+            let cookies = request
+                .headers()
+                .get("cookie")
+                .and_then(|cookie| cookie.to_str().ok())
+                .into_iter()
+                .map(str::to_string)
+                .chain(
+                    options
+                        .ws_cookies
+                        .iter()
+                        .map(|(name, value)| format!("{name}={value}")),
+                )
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            request
+                .headers_mut()
+                .insert("cookie", HeaderValue::from_str(&cookies)?);
+        }
+        request.headers_mut().insert(
+            "sec-websocket-protocol",
+            HeaderValue::from_static(options.ws_protocol.sec_websocket_protocol()),
+        );
+
+        if !options.no_ws_compression {
+            request.headers_mut().insert(
+                "sec-websocket-extensions",
+                HeaderValue::from_str("permessage-deflate; client_max_window_bits")?,
+            );
+
+            request.extensions_mut().insert("permessage-deflate");
+            request.extensions_mut().insert("client_max_window_bits");
+        }
+
+        // the size limit applies to every received frame and message
+        let ws_config = options
+            .max_response_size
+            .map(|max_response_size| WebSocketConfig {
+                max_message_size: Some(max_response_size),
+                max_frame_size: Some(max_response_size),
+                ..Default::default()
+            });
+
+        // the connector is only used for wss endpoints
+        let ws_connector = Connector::Rustls(rustls_client_config(&options.ca_certificates)?);
+
+        emit_lifecycle_event(
+            options,
+            LifecycleEvent::Connecting,
+            json!({ "endpoint": server_endpoint.as_ref() }),
+        );
+        tracing::debug!(
+            endpoint = server_endpoint.as_ref(),
+            headers = %options.mask.headers(request.headers()),
+            "connecting"
+        );
+        let connect = tokio_tungstenite::connect_async_tls_with_config(
+            request,
+            ws_config,
+            false,
+            Some(ws_connector),
+        );
+        let (mut ws_stream, server_response) = match options.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect)
+                .await
+                .map_err(|_| ConnectTimeout { timeout })??,
+            None => connect.await?,
+        };
+
+        let ws_protocol = match options.ws_protocol {
+            WsProtocol::Auto => match server_response
+                .headers()
+                .get("sec-websocket-protocol")
+                .and_then(|value| value.to_str().ok())
+            {
+                Some("graphql-ws") => WsProtocol::GraphqlWs,
+                _ => WsProtocol::GraphqlTransportWs,
+            },
+            ws_protocol => ws_protocol,
+        };
+        tracing::info!(
+            endpoint = server_endpoint.as_ref(),
+            subprotocol = ws_protocol.sec_websocket_protocol(),
+            "websocket connected"
+        );
+
+        if options.print_handshake {
+            eprintln!(
+                "{}",
+                serde_json::to_string_pretty(&handshake_details(&server_response))?
+            );
+        }
+
+        ws_stream
+            .send(Message::text(serde_json::to_string(&json!({
+                "type": "connection_init",
+                "payload": {}
+            }))?))
+            .await?;
+
+        ws_stream
+            .next()
+            .await
+            .ok_or(WsConnectionInitError)?
+            .map_err(|e| ws_read_error(e, options))?;
+        emit_lifecycle_event(
+            options,
+            LifecycleEvent::Connected,
+            json!({
+                "endpoint": server_endpoint.as_ref(),
+                "subprotocol": ws_protocol.sec_websocket_protocol(),
+            }),
+        );
+
+        Ok(Self {
+            ws_stream,
+            ws_protocol,
+            options,
+        })
+    }
+
+    pub async fn subscribe(
+        &mut self,
+        subscription_id: &str,
+        query: Option<&str>,
+        operation_name: Option<&str>,
+        variables: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ws_stream
+            .send(Message::text(serde_json::to_string(&subscribe_message(
+                self.ws_protocol,
+                subscription_id,
+                query,
+                operation_name,
+                variables,
+                self.options,
+            ))?))
+            .await?;
+
+        if let Some(metrics) = self.options.metrics.as_ref() {
+            metrics.record_request_sent();
+        }
+        tracing::debug!(
+            id = subscription_id,
+            variables = %self.options.mask.variables(variables),
+            "subscription started"
+        );
+        emit_lifecycle_event(
+            self.options,
+            LifecycleEvent::Subscribed,
+            json!({ "id": subscription_id }),
+        );
+
+        Ok(())
+    }
+
+    // stops a running subscription, the server answers with a complete message
+    pub async fn complete(
+        &mut self,
+        subscription_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ws_stream
+            .send(Message::text(serde_json::to_string(&json!({
+                "id": subscription_id,
+                "type": self.ws_protocol.complete_message_type(),
+            }))?))
+            .await?;
+
+        Ok(())
+    }
+
+    // the reason of a normal close is the only hint why the subscriptions ended, so it is
+    // printed even though the default log level hides everything below errors
+    fn report_close(&self, code: Option<u16>, reason: &str) {
+        if self.options.emit_lifecycle {
+            emit_lifecycle_event(
+                self.options,
+                LifecycleEvent::Closed,
+                json!({ "code": code, "reason": reason }),
+            );
+        } else if let Some(code) = code {
+            eprintln!("websocket closed by the server: code = {code}, reason = '{reason}'");
+        } else {
+            eprintln!("websocket closed by the server without a close frame");
+        }
+    }
+
+    // nothing is returned after the server closed the connection normally
+    pub async fn next(&mut self) -> Result<Option<WsEvent>, Box<dyn std::error::Error>> {
+        while let Some(message) = self.ws_stream.next().await {
+            match message {
+                Ok(Message::Close(frame)) => {
+                    let Some(frame) = frame else {
+                        self.report_close(None, "");
+                        return Ok(None);
+                    };
+
+                    let code = u16::from(frame.code);
+                    if frame.code == CloseCode::Normal {
+                        self.report_close(Some(code), &frame.reason);
+                        return Ok(None);
+                    }
+
+                    emit_lifecycle_event(
+                        self.options,
+                        LifecycleEvent::Closed,
+                        json!({ "code": code, "reason": frame.reason.to_string() }),
+                    );
+                    return Err(WsClosedByServer {
+                        code,
+                        reason: frame.reason.into_owned(),
+                    }
+                    .into());
+                }
+                Ok(message) => {
+                    let Ok(message) = message.into_text() else {
+                        tracing::error!("Invalid message received from websocket");
+                        continue;
+                    };
+
+                    let message = serde_json::from_str::<serde_json::Value>(&message)?;
+                    let message_type = message["type"].as_str().unwrap_or_default().to_string();
+                    if self.options.ws_passthrough_types.contains(&message_type) {
+                        return Ok(Some(WsEvent::Passthrough {
+                            message_type,
+                            message,
+                        }));
+                    }
+
+                    let response = serde_json::from_value::<WsResponse>(message)?;
+
+                    if let Some(payload) = response.payload {
+                        if let Some(metrics) = self.options.metrics.as_ref() {
+                            metrics.record_event_received();
+                        }
+                        emit_lifecycle_event(
+                            self.options,
+                            LifecycleEvent::Event,
+                            json!({ "id": response.id }),
+                        );
+
+                        return Ok(Some(WsEvent::Next {
+                            subscription_id: response.id,
+                            payload,
+                        }));
+                    } else if response.r#type == "complete" {
+                        emit_lifecycle_event(
+                            self.options,
+                            LifecycleEvent::Completed,
+                            json!({ "id": response.id }),
+                        );
+
+                        return Ok(Some(WsEvent::Complete {
+                            subscription_id: response.id,
+                        }));
+                    }
+                }
+                Err(
+                    e @ tokio_tungstenite::tungstenite::Error::Capacity(
+                        CapacityError::MessageTooLong { .. },
+                    ),
+                ) => {
+                    return Err(ws_read_error(e, self.options));
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "cannot read from the websocket");
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub async fn close(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.ws_stream.close(None).await?;
+
+        Ok(())
+    }
+}
+
+fn subscribe_message(
+    ws_protocol: WsProtocol,
+    subscription_id: &str,
+    query: Option<&str>,
+    operation_name: Option<&str>,
+    variables: &serde_json::Map<String, serde_json::Value>,
+    options: &ClientOptions,
+) -> serde_json::Value {
+    json!({
+        "id": subscription_id,
+        "type": ws_protocol.subscribe_message_type(),
+        "payload": build_request_body(query, operation_name, variables, options),
+    })
+}
+
+async fn try_ws_request(
+    server_endpoint: impl AsRef<str>,
+    headers: HeaderMap,
+    query: Option<String>,
+    operation_name: Option<impl AsRef<str>>,
+    variable_sets: &[serde_json::Map<String, serde_json::Value>],
+    response_processor: &mut impl FnMut(
+        GraphQlResponse,
+        &ResponseMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+    options: &ClientOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut ws_session = WsSession::connect(server_endpoint, &headers, options).await?;
+
+    let mut active_subscriptions = HashMap::new();
+
+    for (index, variables) in variable_sets.iter().enumerate() {
+        let subscription_id = match options.ws_subscription_id.as_ref() {
+            Some(subscription_id) if variable_sets.len() > 1 => {
+                format!("{subscription_id}-{index}")
+            }
+            Some(subscription_id) => subscription_id.clone(),
+            None => Uuid::new_v4().to_string(),
+        };
+
+        ws_session
+            .subscribe(
+                &subscription_id,
+                query.as_deref(),
+                operation_name.as_ref().map(|s| s.as_ref()),
+                variables,
+            )
+            .await?;
+
+        active_subscriptions.insert(subscription_id, (index, variables));
+    }
+
+    while let Some(event) = ws_session.next().await? {
+        match event {
+            WsEvent::Passthrough {
+                message_type,
+                message,
+            } => {
+                // vendor specific frames are forwarded as they are under the extensions
+                response_processor(
+                    GraphQlResponse {
+                        data: None,
+                        extensions: BTreeMap::from([("wsMessage".into(), message)]),
+                        errors: Vec::new(),
+                    },
+                    &ResponseMetadata {
+                        ws_message_type: Some(message_type),
+                        ..Default::default()
+                    },
+                )?;
+            }
+            WsEvent::Next {
+                subscription_id,
+                payload,
+            } => {
+                let subscription = subscription_id
+                    .as_ref()
+                    .and_then(|id| active_subscriptions.get(id));
+                response_processor(
+                    payload,
+                    &ResponseMetadata {
+                        variable_set_index: subscription.map(|(index, _variables)| *index),
+                        variables: subscription
+                            .map(|(_index, variables)| (*variables).clone())
+                            .unwrap_or_default(),
+                        subscription_id,
+                        ..Default::default()
+                    },
+                )?;
+            }
+            WsEvent::Complete { subscription_id } => {
+                if let Some(id) = subscription_id.as_ref() {
+                    active_subscriptions.remove(id);
+                }
+
+                if active_subscriptions.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn ws_read_error(
+    error: tokio_tungstenite::tungstenite::Error,
+    options: &ClientOptions,
+) -> Box<dyn std::error::Error> {
+    match error {
+        tokio_tungstenite::tungstenite::Error::Capacity(CapacityError::MessageTooLong {
+            ..
+        }) => ResponseTooLarge {
+            limit: options.max_response_size.unwrap_or_default(),
+        }
+        .into(),
+        e => e.into(),
+    }
+}
+
+fn handshake_details(
+    server_response: &tokio_tungstenite::tungstenite::handshake::client::Response,
+) -> serde_json::Value {
+    let header = |name: &str| {
+        server_response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    json!({
+        "status": server_response.status().as_u16(),
+        "subprotocol": header("sec-websocket-protocol"),
+        "headers": {
+            "sec-websocket-extensions": header("sec-websocket-extensions"),
+            "sec-websocket-accept": header("sec-websocket-accept"),
+            "server": header("server"),
+        },
+    })
+}
+
+pub async fn ws_request(
+    server_endpoint: impl AsRef<str>,
+    headers: HeaderMap,
+    query: Option<String>,
+    operation_name: Option<impl AsRef<str>>,
+    variable_sets: Vec<serde_json::Map<String, serde_json::Value>>,
+    mut response_processor: impl FnMut(
+        GraphQlResponse,
+        &ResponseMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+    options: &ClientOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut variable_sets = variable_sets;
+
+    // only a failed attempt is followed by a reconnect, the others are resubscribed
+    let mut previous_attempt_failed = false;
+    for attempt in 1.. {
+        if previous_attempt_failed {
+            tracing::info!(attempt, "reconnecting");
+            emit_lifecycle_event(
+                options,
+                LifecycleEvent::Reconnecting,
+                json!({ "attempt": attempt }),
+            );
+            if let Some(metrics) = options.metrics.as_ref() {
+                metrics.record_reconnect();
+            }
+        }
+        let mut attempt_failed = false;
+
+        // the last cursor of each variable set is injected into its variables on resubscribe
+        let mut cursors = vec![None; variable_sets.len()];
+        let result = try_ws_request(
+            server_endpoint.as_ref(),
+            headers.clone(),
+            query.clone(),
+            operation_name.as_ref().map(|s| s.as_ref()),
+            &variable_sets,
+            &mut |response: GraphQlResponse, metadata: &ResponseMetadata| {
+                if let Some(resume_cursor) = options.resume_cursor.as_ref() {
+                    let cursor =
+                        select(&serde_json::to_value(&response)?, &resume_cursor.json_path)
+                            .cloned();
+                    if let (Some(cursor), Some(index)) = (cursor, metadata.variable_set_index) {
+                        if let Some(cursor_file) = resume_cursor.cursor_file.as_ref() {
+                            std::fs::write(cursor_file, serde_json::to_string(&cursor)?)?;
+                        }
+                        cursors[index] = Some(cursor);
+                    }
+                }
+
+                response_processor(response, metadata)
+            },
+            options,
+        )
+        .instrument(tracing::info_span!("ws_session", attempt))
+        .await;
+
+        if let Some(resume_cursor) = options.resume_cursor.as_ref() {
+            for (variables, cursor) in variable_sets.iter_mut().zip(cursors) {
+                if let Some(cursor) = cursor {
+                    tracing::debug!(%cursor, "resubscribing from the cursor");
+                    variables.insert(resume_cursor.variable.clone(), cursor);
+                }
+            }
+        }
+
+        if let Err(e) = result {
+            if let Some(metrics) = options.metrics.as_ref() {
+                metrics.record_error();
+            }
+            emit_lifecycle_event(
+                options,
+                LifecycleEvent::Error,
+                json!({ "attempt": attempt, "message": e.to_string() }),
+            );
+            log_failed_attempt(attempt, e.as_ref(), options);
+            attempt_failed = true;
+        }
+
+        previous_attempt_failed = attempt_failed;
+        if let Some(duration) = options.try_reconnect_duration {
+            tokio::time::sleep(duration).await;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("WsConnectionInitError")]
+    pub struct WsConnectionInitError;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("EmptyResponse: status = {status}, the response has no body")]
+    pub struct EmptyResponse {
+        pub status: u16,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error(
+        "InvalidServerEndpointScheme: endpoint = '{endpoint}', accepted schemes = http, https, ws, wss"
+    )]
+    pub struct InvalidServerEndpointScheme {
+        pub endpoint: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("NoWsEndpoint: endpoint = '{endpoint}', subscriptions need a ws or wss endpoint")]
+    pub struct NoWsEndpoint {
+        pub endpoint: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("ConnectTimeout: timeout = '{timeout:?}'")]
+    pub struct ConnectTimeout {
+        pub timeout: std::time::Duration,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("ResponseTooLarge: limit = {limit} bytes")]
+    pub struct ResponseTooLarge {
+        pub limit: usize,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("RequestTimeout: timeout = '{timeout:?}'")]
+    pub struct RequestTimeout {
+        pub timeout: std::time::Duration,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UnexpectedOperationKind: expected = '{expected}', actual = '{actual:?}'")]
+    pub struct UnexpectedOperationKind {
+        pub expected: crate::operations::OperationKind,
+        pub actual: Option<crate::operations::OperationKind>,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("RetryableGraphQlError: code = '{code}', retries = {retries}")]
+    pub struct RetryableGraphQlError {
+        pub code: String,
+        pub retries: u32,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("OperationNameRequired: reason = '{reason}'")]
+    pub struct OperationNameRequired {
+        pub reason: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("HeaderCommandFailed: name = '{name}', reason = '{reason}'")]
+    pub struct HeaderCommandFailed {
+        pub name: String,
+        pub reason: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UnresolvedResponseReference: json path = '{json_path}'")]
+    pub struct UnresolvedResponseReference {
+        pub json_path: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("PingFailed: status = {status}, errors = {errors}")]
+    pub struct PingFailed {
+        pub status: u16,
+        pub errors: usize,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("WsClosedByServer: code = {code}, reason = '{reason}'")]
+    pub struct WsClosedByServer {
+        pub code: u16,
+        pub reason: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UploadRequiresPost")]
+    pub struct UploadRequiresPost;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("DuplicateFragment: name = '{name}', path = '{path}', the fragment is already defined")]
+    pub struct DuplicateFragment {
+        pub name: String,
+        pub path: std::path::PathBuf,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("ImportCycle: paths = {paths:?}")]
+    pub struct ImportCycle {
+        pub paths: Vec<std::path::PathBuf>,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidVariablesLine: path = '{path}', line = {line}, expected a json object")]
+    pub struct InvalidVariablesLine {
+        pub path: std::path::PathBuf,
+        pub line: usize,
+    }
 
     #[derive(Debug, thiserror::Error)]
-    #[error("InvalidServerEndpointScheme")]
-    pub struct InvalidServerEndpointScheme;
+    #[error(
+        "InvalidVariablesFile: path = '{path}', expected an object or a non-empty array of objects"
+    )]
+    pub struct InvalidVariablesFile {
+        pub path: std::path::PathBuf,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidFrontMatter: path = '{path}', message = '{message}'")]
+    pub struct InvalidFrontMatter {
+        pub path: std::path::PathBuf,
+        pub message: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    // answers the requests with the given raw http responses in order, one connection each
+    async fn serve(responses: Vec<&'static str>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _address) = listener.accept().await.unwrap();
+                read_request(&mut stream).await;
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        format!("http://{address}")
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> Vec<u8> {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        while !is_complete_request(&request) {
+            let read = stream.read(&mut buffer).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+
+        request
+    }
+
+    fn is_complete_request(request: &[u8]) -> bool {
+        let request = String::from_utf8_lossy(request);
+        let Some((head, body)) = request.split_once("\r\n\r\n") else {
+            return false;
+        };
+        let content_length = head
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _value)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_name, value)| value.trim().parse::<usize>().ok())
+            .unwrap_or_default();
+
+        body.len() >= content_length
+    }
+
+    // answers a single request with the given raw http response and hands over the raw request
+    async fn serve_recording(
+        response: &'static str,
+    ) -> (String, tokio::sync::oneshot::Receiver<Vec<u8>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (request_sender, request_receiver) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut stream, _address) = listener.accept().await.unwrap();
+            let request = read_request(&mut stream).await;
+            stream.write_all(response.as_bytes()).await.unwrap();
+            let _ = request_sender.send(request);
+        });
+
+        (format!("http://{address}"), request_receiver)
+    }
+
+    async fn request_once(
+        response: &'static str,
+        options: &ClientOptions,
+    ) -> Result<Option<GraphQlResponse>, Box<dyn std::error::Error>> {
+        let server_endpoint = serve(vec![response]).await;
+        let mut processed_response = None;
+
+        try_http_request(
+            &reqwest::Client::new(),
+            server_endpoint,
+            HeaderMap::new(),
+            Some("mutation { deleteUser(id: 1) }".to_string()),
+            None::<&str>,
+            serde_json::Map::new(),
+            &mut |response, _metadata| {
+                processed_response = Some(response);
+                Ok(())
+            },
+            options,
+        )
+        .await?;
+
+        Ok(processed_response)
+    }
+
+    #[test]
+    fn big_integers_are_printed_unchanged() {
+        // the second id does not fit into an u64 either
+        let body = r#"{"data":{"user":{"accountId":123456789012345678901234567890,"id":9007199254740993}}}"#;
+
+        let response = serde_json::from_str::<GraphQlResponse>(body).unwrap();
+
+        assert_eq!(serde_json::to_string(&response).unwrap(), body);
+    }
+
+    #[test]
+    fn operation_name_is_omitted_with_no_operation_name() {
+        let options = ClientOptions {
+            omit_operation_name: true,
+            ..Default::default()
+        };
+
+        let body = build_request_body(
+            Some("query GetUser { user { id } }"),
+            Some("GetUser"),
+            &serde_json::Map::new(),
+            &options,
+        );
+
+        assert!(body.get("operationName").is_none());
+        assert_eq!(body["query"], "query GetUser { user { id } }");
+    }
+
+    #[test]
+    fn operation_name_is_sent_as_null_by_default() {
+        let body = build_request_body(
+            Some("{ user { id } }"),
+            None,
+            &serde_json::Map::new(),
+            &ClientOptions::default(),
+        );
+
+        assert_eq!(body.get("operationName"), Some(&serde_json::Value::Null));
+    }
+
+    #[test]
+    fn operation_name_is_omitted_from_the_subscribe_payload() {
+        let options = ClientOptions {
+            omit_operation_name: true,
+            ..Default::default()
+        };
+
+        let message = subscribe_message(
+            WsProtocol::GraphqlTransportWs,
+            "1",
+            Some("subscription OnEvent { event }"),
+            Some("OnEvent"),
+            &serde_json::Map::new(),
+            &options,
+        );
+
+        assert_eq!(message["type"], "subscribe");
+        assert!(message["payload"].get("operationName").is_none());
+    }
+
+    #[test]
+    fn deadline_is_computed_for_every_attempt() {
+        let options = ClientOptions {
+            deadline: Some(Deadline {
+                duration: std::time::Duration::from_secs(5),
+                header: HeaderName::from_static("x-request-deadline"),
+                format: DeadlineFormat::UnixMillis,
+            }),
+            ..Default::default()
+        };
+        let deadline = |headers: HeaderMap| -> u128 {
+            headers["x-request-deadline"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+
+        let first_deadline = deadline(attempt_headers(&HeaderMap::new(), &options).unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let second_deadline = deadline(attempt_headers(&HeaderMap::new(), &options).unwrap());
+
+        assert!(second_deadline >= first_deadline + 20);
+    }
+
+    #[test]
+    fn form_body_has_the_fields_with_stringified_variables() {
+        let options = ClientOptions {
+            request_encoding: RequestEncoding::Form,
+            ..Default::default()
+        };
+        let variables = json!({"filter": {"name": "a&b"}, "id": 7});
+
+        let body = build_request_body(
+            Some("query GetUser($id: Int) { user(id: $id) { id } }"),
+            Some("GetUser"),
+            variables.as_object().unwrap(),
+            &options,
+        );
+        let (content_type, encoded_body) = encode_request_body(&body, &options).unwrap();
+
+        assert_eq!(content_type, "application/x-www-form-urlencoded");
+        let fields = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&encoded_body).unwrap();
+        assert_eq!(
+            fields,
+            [
+                ("operationName".to_string(), "GetUser".to_string()),
+                (
+                    "query".to_string(),
+                    "query GetUser($id: Int) { user(id: $id) { id } }".to_string()
+                ),
+                (
+                    "variables".to_string(),
+                    r#"{"filter":{"name":"a&b"},"id":7}"#.to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn form_body_skips_the_null_operation_name() {
+        let options = ClientOptions {
+            request_encoding: RequestEncoding::Form,
+            ..Default::default()
+        };
+
+        let body = build_request_body(
+            Some("{ user { id } }"),
+            None,
+            &serde_json::Map::new(),
+            &options,
+        );
+        let (_content_type, encoded_body) = encode_request_body(&body, &options).unwrap();
+
+        assert_eq!(
+            String::from_utf8(encoded_body).unwrap(),
+            "query=%7B+user+%7B+id+%7D+%7D&variables=%7B%7D"
+        );
+    }
+
+    #[tokio::test]
+    async fn signature_covers_the_exact_bytes_that_are_sent() {
+        let hmac_signer = HmacSigner {
+            header: HeaderName::from_static("x-signature"),
+            secret: b"secret".to_vec(),
+        };
+        for request_encoding in [RequestEncoding::Json, RequestEncoding::Form] {
+            let options = ClientOptions {
+                request_encoding,
+                hmac_signer: Some(hmac_signer.clone()),
+                ..Default::default()
+            };
+            let (server_endpoint, request) =
+                serve_recording("HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await;
+            let variables =
+                serde_json::json!({ "id": 123456789012345678901234567890u128, "name": "a&b" });
+
+            try_http_request(
+                &reqwest::Client::new(),
+                server_endpoint,
+                HeaderMap::new(),
+                Some("query GetUser($id: Int) { user(id: $id) { id } }".to_string()),
+                None::<&str>,
+                variables.as_object().unwrap().clone(),
+                &mut |_response, _metadata| Ok(()),
+                &options,
+            )
+            .await
+            .unwrap();
+
+            let request = request.await.unwrap();
+            let header_end = request
+                .windows(4)
+                .position(|window| window == b"\r\n\r\n")
+                .unwrap();
+            let (head, body) = (
+                String::from_utf8_lossy(&request[..header_end]),
+                &request[header_end + 4..],
+            );
+            let signature = head
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _value)| name.eq_ignore_ascii_case("x-signature"))
+                .map(|(_name, value)| value.trim().to_string())
+                .unwrap();
+
+            assert!(!body.is_empty());
+            assert_eq!(signature, hmac_signer.sign(body).to_str().unwrap());
+        }
+    }
+
+    const NO_CONTENT_RESPONSE: &str = "HTTP/1.1 204 No Content\r\n\r\n";
+    const EMPTY_BODY_RESPONSE: &str = "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+
+    #[tokio::test]
+    async fn no_content_is_a_response_without_data() {
+        for response in [NO_CONTENT_RESPONSE, EMPTY_BODY_RESPONSE] {
+            let response = request_once(response, &ClientOptions::default())
+                .await
+                .unwrap()
+                .expect("the empty response is processed");
+
+            assert!(response.data.is_none());
+            assert!(response.errors.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn no_content_is_an_error_with_strict() {
+        let options = ClientOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        for (response, expected_status) in [(NO_CONTENT_RESPONSE, 204), (EMPTY_BODY_RESPONSE, 200)]
+        {
+            let Err(error) = request_once(response, &options).await else {
+                panic!("the empty response is accepted with strict");
+            };
+
+            let error = error
+                .downcast_ref::<EmptyResponse>()
+                .expect("the error is EmptyResponse");
+            assert_eq!(error.status, expected_status);
+        }
+    }
+
+    #[tokio::test]
+    async fn encoded_body_is_passed_on_with_no_decompress() {
+        let options = ClientOptions {
+            no_decompress: true,
+            ..Default::default()
+        };
+        let server_endpoint =
+            serve(vec!["HTTP/1.1 200 OK\r\ncontent-encoding: gzip\r\ncontent-length: 4\r\n\r\n\x1f\x7f\x08\x00"]).await;
+        let client = reqwest::ClientBuilder::new().no_gzip().build().unwrap();
+        let mut raw_body = None;
+
+        try_http_request(
+            &client,
+            server_endpoint,
+            HeaderMap::new(),
+            Some("{ id }".to_string()),
+            None::<&str>,
+            serde_json::Map::new(),
+            &mut |response, metadata| {
+                assert!(response.data.is_none());
+                raw_body = metadata.raw_body.clone();
+                Ok(())
+            },
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(raw_body.as_deref(), Some(b"\x1f\x7f\x08\x00".as_slice()));
+    }
+
+    #[tokio::test]
+    async fn endpoint_is_validated_before_the_query_is_loaded() {
+        let result = execute(
+            "ftp://localhost",
+            HeaderMap::new(),
+            Some(std::env::temp_dir().join(format!("{}.graphql", Uuid::new_v4()))),
+            None::<&str>,
+            vec![serde_json::Map::new()],
+            |_response, _metadata| Ok(()),
+            &ClientOptions::default(),
+        )
+        .await;
+
+        let Err(error) = result else {
+            panic!("the endpoint is accepted");
+        };
+        assert!(error.is::<InvalidServerEndpointScheme>(), "{error:?}");
+    }
+
+    const UNAVAILABLE_RESPONSE: &str = "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 64\r\n\r\n{\"errors\":[{\"message\":\"a\",\"extensions\":{\"code\":\"UNAVAILABLE\"}}]}";
+    const DATA_RESPONSE: &str =
+        "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 19\r\n\r\n{\"data\":{\"id\":\"1\"}}";
+
+    async fn request_with_retries(
+        responses: Vec<&'static str>,
+        variable_sets: usize,
+        fail_fast: bool,
+    ) -> (Vec<GraphQlResponse>, Result<(), Box<dyn std::error::Error>>) {
+        let options = ClientOptions {
+            retry_on_graphql_error: vec!["UNAVAILABLE".to_string()],
+            retry_on_graphql_error_max: 2,
+            retry_on_graphql_error_backoff: std::time::Duration::from_millis(1),
+            fail_fast,
+            ..Default::default()
+        };
+        let server_endpoint = serve(responses).await;
+        let mut processed_responses = Vec::new();
+
+        let result = http_request(
+            server_endpoint,
+            HeaderMap::new(),
+            Some("{ id }".to_string()),
+            None::<&str>,
+            (0..variable_sets)
+                .map(|index| serde_json::Map::from_iter([("index".to_string(), json!(index))]))
+                .collect(),
+            |response, _metadata| {
+                processed_responses.push(response);
+                Ok(())
+            },
+            &options,
+        )
+        .await;
+
+        (processed_responses, result)
+    }
+
+    #[tokio::test]
+    async fn retryable_graphql_error_is_sent_again() {
+        let (responses, result) = request_with_retries(
+            vec![UNAVAILABLE_RESPONSE, UNAVAILABLE_RESPONSE, DATA_RESPONSE],
+            1,
+            false,
+        )
+        .await;
+
+        result.unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].data, Some(json!({"id": "1"})));
+    }
+
+    #[tokio::test]
+    async fn last_retryable_graphql_error_is_returned_with_fail_fast() {
+        let (responses, result) =
+            request_with_retries(vec![UNAVAILABLE_RESPONSE; 3], 1, true).await;
+
+        assert!(responses.is_empty());
+        let Err(error) = result else {
+            panic!("the retries did not run out");
+        };
+        let error = error
+            .downcast_ref::<RetryableGraphQlError>()
+            .expect("the error is RetryableGraphQlError");
+        assert_eq!(error.code, "UNAVAILABLE");
+        assert_eq!(error.retries, 2);
+    }
+
+    #[test]
+    fn retry_backoff_is_doubled_up_to_the_maximum() {
+        let backoff = std::time::Duration::from_millis(500);
+
+        assert_eq!(retry_backoff(backoff, 0), backoff);
+        assert_eq!(retry_backoff(backoff, 3), std::time::Duration::from_secs(4));
+        assert_eq!(retry_backoff(backoff, 10), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(backoff, 31), MAX_RETRY_BACKOFF);
+        assert_eq!(
+            retry_backoff(std::time::Duration::from_nanos(1), 32),
+            std::time::Duration::from_nanos(1 << 32)
+        );
+        assert_eq!(
+            retry_backoff(std::time::Duration::from_nanos(1), 100),
+            MAX_RETRY_BACKOFF
+        );
+        assert_eq!(
+            retry_backoff(std::time::Duration::ZERO, 100),
+            std::time::Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_retries_do_not_stop_the_other_variable_sets() {
+        let (responses, result) = request_with_retries(
+            vec![
+                UNAVAILABLE_RESPONSE,
+                UNAVAILABLE_RESPONSE,
+                UNAVAILABLE_RESPONSE,
+                DATA_RESPONSE,
+            ],
+            2,
+            false,
+        )
+        .await;
+
+        result.unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].data, Some(json!({"id": "1"})));
+    }
+
+    fn variables_file(contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("graphql-cli-tools-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    fn merged_variables(
+        precedence: VariablesPrecedence,
+        deep_merge: bool,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let file = variables_file(r#"{"user": {"name": "file", "role": "admin"}, "limit": 10}"#);
+        let variables_list = vec![
+            (
+                "user".to_string(),
+                json!({"name": "cli", "email": "cli@example.com"}),
+            ),
+            ("limit".to_string(), json!(20)),
+        ];
+
+        let mut variable_sets = load_variable_sets(
+            vec![file],
+            variables_list,
+            VariablesMerge {
+                precedence,
+                deep_merge,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(variable_sets.len(), 1);
+        variable_sets.remove(0)
+    }
+
+    #[test]
+    fn cli_variables_replace_file_variables() {
+        let variables = merged_variables(VariablesPrecedence::CliWins, false);
+
+        assert_eq!(
+            json!(variables),
+            json!({"user": {"name": "cli", "email": "cli@example.com"}, "limit": 20})
+        );
+    }
+
+    #[test]
+    fn cli_variables_are_deep_merged_over_file_variables() {
+        let variables = merged_variables(VariablesPrecedence::CliWins, true);
+
+        assert_eq!(
+            json!(variables),
+            json!({"user": {"name": "cli", "role": "admin", "email": "cli@example.com"}, "limit": 20})
+        );
+    }
+
+    #[test]
+    fn file_variables_are_kept_over_cli_variables() {
+        let variables = merged_variables(VariablesPrecedence::FileWins, false);
+
+        assert_eq!(
+            json!(variables),
+            json!({"user": {"name": "file", "role": "admin"}, "limit": 10})
+        );
+    }
+
+    #[test]
+    fn cli_variables_are_deep_merged_under_file_variables() {
+        let variables = merged_variables(VariablesPrecedence::FileWins, true);
+
+        assert_eq!(
+            json!(variables),
+            json!({"user": {"name": "file", "role": "admin", "email": "cli@example.com"}, "limit": 10})
+        );
+    }
+
+    #[test]
+    fn empty_variable_file_array_is_invalid() {
+        let result = load_variable_sets(
+            vec![variables_file("[]")],
+            Vec::new(),
+            VariablesMerge::default(),
+        );
+
+        let Err(error) = result else {
+            panic!("the empty array is accepted");
+        };
+        assert!(error.is::<InvalidVariablesFile>(), "{error:?}");
+    }
+
+    #[test]
+    fn later_variable_files_override_earlier_ones() {
+        let files = vec![
+            variables_file(r#"{"first": 10, "after": "a", "filter": {"role": "admin"}}"#),
+            variables_file(r#"{"first": 20, "filter": {"name": "second"}}"#),
+            variables_file(r#"{"after": "c"}"#),
+        ];
+
+        let variable_sets =
+            load_variable_sets(files, Vec::new(), VariablesMerge::default()).unwrap();
+
+        assert_eq!(
+            json!(variable_sets),
+            json!([{"first": 20, "after": "c", "filter": {"name": "second"}}])
+        );
+    }
+
+    #[test]
+    fn cli_variables_override_layered_variable_files() {
+        let files = vec![
+            variables_file(r#"{"first": 10, "after": "a"}"#),
+            variables_file(r#"{"first": 20}"#),
+        ];
+
+        let variable_sets = load_variable_sets(
+            files,
+            vec![("after".to_string(), json!("cli"))],
+            VariablesMerge::default(),
+        )
+        .unwrap();
+
+        assert_eq!(json!(variable_sets), json!([{"first": 20, "after": "cli"}]));
+    }
+
+    #[test]
+    fn layered_variable_files_multiply_the_variable_sets() {
+        let files = vec![
+            variables_file(r#"{"first": 10, "after": "a"}"#),
+            variables_file(r#"[{"after": "b"}, {"after": "c"}]"#),
+        ];
+
+        let variable_sets = load_variable_sets(
+            files,
+            vec![("first".to_string(), json!(5))],
+            VariablesMerge::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            json!(variable_sets),
+            json!([{"first": 5, "after": "b"}, {"first": 5, "after": "c"}])
+        );
+    }
+
+    fn query_file(contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("graphql-cli-tools-{}.graphql", Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn yaml_front_matter_provides_the_default_variables() {
+        let path = query_file("---\nlimit: 10\nuser:\n  name: a\n---\nquery Users { users }\n");
+
+        let (query, default_variables) = load_query_with_front_matter(&path).unwrap();
+
+        // the front-matter is replaced with empty lines
+        assert_eq!(query, "\n\n\n\n\nquery Users { users }\n");
+        assert_eq!(
+            json!(default_variables),
+            json!({"limit": 10, "user": {"name": "a"}})
+        );
+    }
+
+    #[test]
+    fn json_front_matter_provides_the_default_variables() {
+        let path = query_file("---\n{\"limit\": 10}\n---\nquery Users { users }\n");
+
+        let (_query, default_variables) = load_query_with_front_matter(&path).unwrap();
+
+        assert_eq!(json!(default_variables), json!({"limit": 10}));
+    }
+
+    #[test]
+    fn query_without_front_matter_has_no_default_variables() {
+        let path = query_file("query Users { users }\n");
+
+        let (query, default_variables) = load_query_with_front_matter(&path).unwrap();
+
+        assert_eq!(query, "query Users { users }\n");
+        assert!(default_variables.is_empty());
+    }
+
+    #[test]
+    fn invalid_front_matter_carries_the_parser_error() {
+        let path = query_file("---\nlimit: [10\n---\nquery Users { users }\n");
+
+        let e = load_query_with_front_matter(&path).unwrap_err();
+        let e = e.downcast_ref::<InvalidFrontMatter>().unwrap();
+
+        assert_eq!(e.path, path);
+        assert!(!e.message.is_empty());
+    }
+
+    #[test]
+    fn front_matter_that_is_not_an_object_is_invalid() {
+        let path = query_file("---\n- 10\n---\nquery Users { users }\n");
+
+        let e = load_query_with_front_matter(&path).unwrap_err();
+
+        assert!(e.downcast_ref::<InvalidFrontMatter>().is_some());
+    }
+
+    type WsServerRequest = tokio_tungstenite::tungstenite::handshake::server::Request;
+    type WsServerResponse = tokio_tungstenite::tungstenite::handshake::server::Response;
+
+    // the first connection sends a cursor for the second subscription and closes, the subscribe
+    // messages of the second connection are handed over
+    #[allow(clippy::result_large_err)]
+    async fn serve_ws_cursor() -> (
+        String,
+        tokio::sync::oneshot::Receiver<Vec<serde_json::Value>>,
+    ) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (subscribes_sender, subscribes_receiver) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            for connection in 0..2 {
+                let (stream, _address) = listener.accept().await.unwrap();
+                let mut ws_stream = tokio_tungstenite::accept_hdr_async(
+                    stream,
+                    |_request: &WsServerRequest, mut response: WsServerResponse| {
+                        response.headers_mut().insert(
+                            "sec-websocket-protocol",
+                            HeaderValue::from_static("graphql-transport-ws"),
+                        );
+                        Ok(response)
+                    },
+                )
+                .await
+                .unwrap();
+
+                let mut subscribes = Vec::new();
+                while subscribes.len() < 2 {
+                    let message = ws_stream.next().await.unwrap().unwrap();
+                    let message =
+                        serde_json::from_str::<serde_json::Value>(message.to_text().unwrap())
+                            .unwrap();
+                    match message["type"].as_str() {
+                        Some("connection_init") => {
+                            let ack = json!({"type": "connection_ack"});
+                            ws_stream
+                                .send(Message::text(ack.to_string()))
+                                .await
+                                .unwrap();
+                        }
+                        Some("subscribe") => subscribes.push(message),
+                        _ => {}
+                    }
+                }
+
+                if connection == 0 {
+                    let next = json!({
+                        "type": "next",
+                        "id": subscribes[1]["id"],
+                        "payload": {"data": {"event": {"cursor": "b"}}},
+                    });
+                    ws_stream
+                        .send(Message::text(next.to_string()))
+                        .await
+                        .unwrap();
+                    ws_stream.close(None).await.unwrap();
+                } else {
+                    subscribes_sender.send(subscribes).unwrap();
+                    return;
+                }
+            }
+        });
+
+        (format!("ws://{address}"), subscribes_receiver)
+    }
+
+    #[tokio::test]
+    async fn cursor_is_resubscribed_with_its_own_variable_set() {
+        let options = ClientOptions {
+            resume_cursor: Some(ResumeCursor {
+                json_path: "$.data.event.cursor".to_string(),
+                variable: "cursor".to_string(),
+                cursor_file: None,
+            }),
+            try_reconnect_duration: Some(std::time::Duration::from_millis(10)),
+            ..Default::default()
+        };
+        let (server_endpoint, subscribes) = serve_ws_cursor().await;
+
+        // the variable sets are identical, so only the subscription tells them apart
+        let client = ws_request(
+            server_endpoint,
+            HeaderMap::new(),
+            Some("subscription { event { cursor } }".to_string()),
+            None::<&str>,
+            vec![serde_json::Map::new(), serde_json::Map::new()],
+            |_response, _metadata| Ok(()),
+            &options,
+        );
+        // the client keeps reconnecting, so it is dropped once the server saw the resubscribe
+        let subscribes = tokio::select! {
+            _result = client => panic!("the client stopped reconnecting"),
+            subscribes = subscribes => subscribes.unwrap(),
+        };
+
+        assert_eq!(subscribes[0]["payload"]["variables"], json!({}));
+        assert_eq!(
+            subscribes[1]["payload"]["variables"],
+            json!({"cursor": "b"})
+        );
+    }
 }
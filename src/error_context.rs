@@ -0,0 +1,48 @@
+use std::fmt::Write;
+
+pub fn render_error_context(
+    query: &str,
+    error: &serde_json::Map<String, serde_json::Value>,
+) -> String {
+    let mut rendered = String::new();
+
+    let message = error
+        .get("message")
+        .and_then(|message| message.as_str())
+        .unwrap_or("<no message>");
+    let _ = writeln!(rendered, "error: {message}");
+
+    let locations = error
+        .get("locations")
+        .and_then(|locations| locations.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|location| {
+            Some((
+                location.get("line")?.as_u64()? as usize,
+                location.get("column")?.as_u64()? as usize,
+            ))
+        });
+
+    for (line, column) in locations {
+        let _ = writeln!(rendered, "  --> {line}:{column}");
+
+        let Some(source_line) = line
+            .checked_sub(1)
+            .and_then(|index| query.lines().nth(index))
+        else {
+            continue;
+        };
+
+        let gutter = " ".repeat(line.to_string().len());
+        let _ = writeln!(rendered, "{gutter} |");
+        let _ = writeln!(rendered, "{line} | {source_line}");
+        let _ = writeln!(
+            rendered,
+            "{gutter} | {}^",
+            " ".repeat(column.saturating_sub(1))
+        );
+    }
+
+    rendered
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+
+use error::InvalidUploadVariablePath;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct FileUploadDir {
+    pub variable_path: String,
+    pub dir: PathBuf,
+}
+
+impl FileUploadDir {
+    pub fn files(&self) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+}
+
+pub struct MultipartBody {
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+// https://github.com/jaydenseric/graphql-multipart-request-spec
+pub fn build_multipart_body(
+    mut operations: serde_json::Value,
+    upload_dirs: &[FileUploadDir],
+) -> Result<MultipartBody, Box<dyn std::error::Error>> {
+    let mut map = serde_json::Map::new();
+    let mut files = Vec::new();
+
+    for upload_dir in upload_dirs {
+        let dir_files = upload_dir.files()?;
+
+        *variable_slot(&mut operations, &upload_dir.variable_path)? =
+            serde_json::Value::Array(vec![serde_json::Value::Null; dir_files.len()]);
+
+        for (index, file) in dir_files.into_iter().enumerate() {
+            map.insert(
+                files.len().to_string(),
+                serde_json::json!([format!("variables.{}.{index}", upload_dir.variable_path)]),
+            );
+            files.push(file);
+        }
+    }
+
+    let boundary = format!("graphql-cli-tools-{}", Uuid::new_v4().simple());
+    let mut body = Vec::new();
+
+    write_part(
+        &mut body,
+        &boundary,
+        "operations",
+        None,
+        serde_json::to_string(&operations)?.as_bytes(),
+    );
+    write_part(
+        &mut body,
+        &boundary,
+        "map",
+        None,
+        serde_json::to_string(&map)?.as_bytes(),
+    );
+    for (index, file) in files.iter().enumerate() {
+        write_part(
+            &mut body,
+            &boundary,
+            &index.to_string(),
+            Some(file),
+            &std::fs::read(file)?,
+        );
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    Ok(MultipartBody {
+        content_type: format!("multipart/form-data; boundary={boundary}"),
+        body,
+    })
+}
+
+fn variable_slot<'a>(
+    operations: &'a mut serde_json::Value,
+    variable_path: &str,
+) -> Result<&'a mut serde_json::Value, InvalidUploadVariablePath> {
+    let invalid_path = || InvalidUploadVariablePath {
+        path: variable_path.to_string(),
+    };
+
+    let mut slot = operations
+        .as_object_mut()
+        .ok_or_else(invalid_path)?
+        .entry("variables")
+        .or_insert_with(|| serde_json::json!({}));
+
+    for segment in variable_path.split('.') {
+        if segment.is_empty() {
+            return Err(invalid_path());
+        }
+        if slot.is_null() {
+            *slot = serde_json::json!({});
+        }
+
+        slot = slot
+            .as_object_mut()
+            .ok_or_else(invalid_path)?
+            .entry(segment)
+            .or_insert(serde_json::Value::Null);
+    }
+
+    Ok(slot)
+}
+
+fn write_part(
+    body: &mut Vec<u8>,
+    boundary: &str,
+    name: &str,
+    file: Option<&Path>,
+    contents: &[u8],
+) {
+    body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+    match file {
+        Some(file) => {
+            let file_name = file
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().replace('"', "%22"))
+                .unwrap_or_default();
+            body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\n\
+                     Content-Type: application/octet-stream\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+        None => {
+            body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{name}\"\r\n\
+                     Content-Type: application/json\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+    }
+    body.extend_from_slice(contents);
+    body.extend_from_slice(b"\r\n");
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidUploadVariablePath: path = '{path}'")]
+    pub struct InvalidUploadVariablePath {
+        pub path: String,
+    }
+}
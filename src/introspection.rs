@@ -0,0 +1,869 @@
+use std::fmt::Write;
+
+use error::{IntrospectionRequestFailed, InvalidIntrospectionResult};
+use reqwest::header::{HeaderMap, HeaderValue};
+
+use crate::{
+    client::{try_http_request, ClientOptions, GraphQlResponse},
+    operations::{infer_operation_name, OperationKind},
+};
+
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      isRepeatable
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+const BUILTIN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+const BUILTIN_DIRECTIVES: [&str; 5] = ["skip", "include", "deprecated", "specifiedBy", "oneOf"];
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionSchema {
+    pub query_type: Option<NamedTypeRef>,
+    pub mutation_type: Option<NamedTypeRef>,
+    pub subscription_type: Option<NamedTypeRef>,
+    pub types: Vec<IntrospectionType>,
+    #[serde(default)]
+    pub directives: Vec<IntrospectionDirective>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct NamedTypeRef {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionType {
+    pub kind: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: Option<Vec<IntrospectionField>>,
+    pub input_fields: Option<Vec<IntrospectionInputValue>>,
+    pub interfaces: Option<Vec<TypeRef>>,
+    pub enum_values: Option<Vec<IntrospectionEnumValue>>,
+    pub possible_types: Option<Vec<TypeRef>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionField {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub args: Vec<IntrospectionInputValue>,
+    #[serde(rename = "type")]
+    pub ty: TypeRef,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionInputValue {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub ty: TypeRef,
+    pub default_value: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionEnumValue {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionDirective {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_repeatable: bool,
+    pub locations: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<IntrospectionInputValue>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeRef {
+    pub kind: String,
+    pub name: Option<String>,
+    pub of_type: Option<Box<TypeRef>>,
+}
+
+impl std::fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.kind.as_str(), self.of_type.as_ref()) {
+            ("NON_NULL", Some(of_type)) => write!(f, "{of_type}!"),
+            ("LIST", Some(of_type)) => write!(f, "[{of_type}]"),
+            _ => write!(f, "{}", self.name.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+    // the type name or 'Type.field'
+    pub label: String,
+    pub kind: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SdlOptions {
+    pub sort: bool,
+    pub include_builtins: bool,
+    pub no_descriptions: bool,
+}
+
+impl IntrospectionSchema {
+    // accepts a whole response ({"data": {"__schema": ...}}), its data or the schema itself
+    pub fn from_json(value: serde_json::Value) -> Result<Self, InvalidIntrospectionResult> {
+        let schema = match value {
+            serde_json::Value::Object(mut object) => match object.remove("data") {
+                // e.g. the data is null when the server answers with errors only
+                Some(mut data) => data
+                    .as_object_mut()
+                    .and_then(|data| data.remove("__schema"))
+                    .ok_or_else(|| InvalidIntrospectionResult {
+                        reason: "the data is not an object with a __schema field".into(),
+                    })?,
+                None => match object.remove("__schema") {
+                    Some(schema) => schema,
+                    None => serde_json::Value::Object(object),
+                },
+            },
+            value => value,
+        };
+
+        serde_json::from_value(schema).map_err(|e| InvalidIntrospectionResult {
+            reason: e.to_string(),
+        })
+    }
+
+    pub fn to_sdl(&self, options: &SdlOptions) -> String {
+        let mut blocks = Vec::new();
+
+        if let Some(schema_block) = self.render_schema_block() {
+            blocks.push(schema_block);
+        }
+
+        let mut directives = self
+            .directives
+            .iter()
+            .filter(|directive| {
+                options.include_builtins || !BUILTIN_DIRECTIVES.contains(&directive.name.as_str())
+            })
+            .collect::<Vec<_>>();
+        if options.sort {
+            directives.sort_by_key(|directive| directive.name.as_str());
+        }
+        blocks.extend(
+            directives
+                .into_iter()
+                .map(|directive| render_directive(directive, options)),
+        );
+
+        let mut types = self
+            .types
+            .iter()
+            .filter(|r#type| options.include_builtins || !is_builtin_type(&r#type.name))
+            .collect::<Vec<_>>();
+        if options.sort {
+            types.sort_by_key(|r#type| r#type.name.as_str());
+        }
+        blocks.extend(types.into_iter().map(|r#type| render_type(r#type, options)));
+
+        blocks.join("\n")
+    }
+
+    // every type is followed by its fields, the built-in types are left out
+    pub fn completion_items(&self, root: Option<OperationKind>) -> Vec<CompletionItem> {
+        let root_type_name = root.map(|root| {
+            let (root_type, default_name) = match root {
+                OperationKind::Query => (self.query_type.as_ref(), "Query"),
+                OperationKind::Mutation => (self.mutation_type.as_ref(), "Mutation"),
+                OperationKind::Subscription => (self.subscription_type.as_ref(), "Subscription"),
+            };
+
+            root_type.map_or(default_name, |root_type| root_type.name.as_str())
+        });
+
+        self.types
+            .iter()
+            .filter(|r#type| !is_builtin_type(&r#type.name))
+            .filter(|r#type| {
+                root_type_name.is_none_or(|root_type_name| r#type.name == root_type_name)
+            })
+            .flat_map(|r#type| {
+                let fields = r#type
+                    .fields
+                    .iter()
+                    .flatten()
+                    .map(|field| (field.name.as_str(), &field.ty))
+                    .chain(
+                        r#type
+                            .input_fields
+                            .iter()
+                            .flatten()
+                            .map(|input_field| (input_field.name.as_str(), &input_field.ty)),
+                    )
+                    .map(|(field_name, field_type)| CompletionItem {
+                        label: format!("{}.{field_name}", r#type.name),
+                        kind: "FIELD".into(),
+                        ty: Some(field_type.to_string()),
+                    });
+
+                std::iter::once(CompletionItem {
+                    label: r#type.name.clone(),
+                    kind: r#type.kind.clone(),
+                    ty: None,
+                })
+                .chain(fields)
+            })
+            .collect()
+    }
+
+    fn render_schema_block(&self) -> Option<String> {
+        let roots = [
+            ("query", self.query_type.as_ref(), "Query"),
+            ("mutation", self.mutation_type.as_ref(), "Mutation"),
+            (
+                "subscription",
+                self.subscription_type.as_ref(),
+                "Subscription",
+            ),
+        ];
+
+        // the schema block can be omitted when the root types use the default names
+        if roots.iter().all(|(_operation, root, default_name)| {
+            root.is_none_or(|root| root.name == *default_name)
+        }) {
+            return None;
+        }
+
+        let mut rendered = String::from("schema {\n");
+        for (operation, root, _default_name) in roots {
+            if let Some(root) = root {
+                let _ = writeln!(rendered, "  {operation}: {}", root.name);
+            }
+        }
+        rendered.push_str("}\n");
+
+        Some(rendered)
+    }
+}
+
+pub fn is_builtin_type(name: &str) -> bool {
+    name.starts_with("__") || BUILTIN_SCALARS.contains(&name)
+}
+
+fn render_directive(directive: &IntrospectionDirective, options: &SdlOptions) -> String {
+    let mut rendered = String::new();
+    render_description(&mut rendered, directive.description.as_deref(), "", options);
+
+    let _ = write!(
+        rendered,
+        "directive @{}{}{} on {}",
+        directive.name,
+        render_arguments(&directive.args, options),
+        if directive.is_repeatable {
+            " repeatable"
+        } else {
+            ""
+        },
+        directive.locations.join(" | "),
+    );
+    rendered.push('\n');
+
+    rendered
+}
+
+fn render_type(r#type: &IntrospectionType, options: &SdlOptions) -> String {
+    let mut rendered = String::new();
+    render_description(&mut rendered, r#type.description.as_deref(), "", options);
+
+    let implements = r#type
+        .interfaces
+        .iter()
+        .flatten()
+        .map(|interface| interface.to_string())
+        .collect::<Vec<_>>();
+    let implements = if implements.is_empty() {
+        String::new()
+    } else {
+        format!(" implements {}", implements.join(" & "))
+    };
+
+    match r#type.kind.as_str() {
+        "SCALAR" => {
+            let _ = writeln!(rendered, "scalar {}", r#type.name);
+        }
+        "OBJECT" | "INTERFACE" => {
+            let keyword = if r#type.kind == "OBJECT" {
+                "type"
+            } else {
+                "interface"
+            };
+            let _ = writeln!(rendered, "{keyword} {}{implements} {{", r#type.name);
+
+            let mut fields = r#type.fields.iter().flatten().collect::<Vec<_>>();
+            if options.sort {
+                fields.sort_by_key(|field| field.name.as_str());
+            }
+            for field in fields {
+                render_description(&mut rendered, field.description.as_deref(), "  ", options);
+                let _ = writeln!(
+                    rendered,
+                    "  {}{}: {}{}",
+                    field.name,
+                    render_arguments(&field.args, options),
+                    field.ty,
+                    render_deprecation(field.is_deprecated, field.deprecation_reason.as_deref()),
+                );
+            }
+
+            rendered.push_str("}\n");
+        }
+        "UNION" => {
+            let members = r#type
+                .possible_types
+                .iter()
+                .flatten()
+                .map(|member| member.to_string())
+                .collect::<Vec<_>>();
+            let _ = writeln!(rendered, "union {} = {}", r#type.name, members.join(" | "));
+        }
+        "ENUM" => {
+            let _ = writeln!(rendered, "enum {} {{", r#type.name);
+
+            let mut values = r#type.enum_values.iter().flatten().collect::<Vec<_>>();
+            if options.sort {
+                values.sort_by_key(|value| value.name.as_str());
+            }
+            for value in values {
+                render_description(&mut rendered, value.description.as_deref(), "  ", options);
+                let _ = writeln!(
+                    rendered,
+                    "  {}{}",
+                    value.name,
+                    render_deprecation(value.is_deprecated, value.deprecation_reason.as_deref()),
+                );
+            }
+
+            rendered.push_str("}\n");
+        }
+        "INPUT_OBJECT" => {
+            let _ = writeln!(rendered, "input {} {{", r#type.name);
+
+            let mut fields = r#type.input_fields.iter().flatten().collect::<Vec<_>>();
+            if options.sort {
+                fields.sort_by_key(|field| field.name.as_str());
+            }
+            for field in fields {
+                render_description(&mut rendered, field.description.as_deref(), "  ", options);
+                let _ = writeln!(rendered, "  {}", render_input_value(field));
+            }
+
+            rendered.push_str("}\n");
+        }
+        kind => {
+            let _ = writeln!(
+                rendered,
+                "# unknown kind '{kind}' of type '{}'",
+                r#type.name
+            );
+        }
+    }
+
+    rendered
+}
+
+fn render_arguments(arguments: &[IntrospectionInputValue], options: &SdlOptions) -> String {
+    if arguments.is_empty() {
+        return String::new();
+    }
+
+    let has_descriptions = !options.no_descriptions
+        && arguments
+            .iter()
+            .any(|argument| argument.description.is_some());
+
+    if has_descriptions {
+        let mut rendered = String::from("(\n");
+        for argument in arguments {
+            render_description(
+                &mut rendered,
+                argument.description.as_deref(),
+                "    ",
+                options,
+            );
+            let _ = writeln!(rendered, "    {}", render_input_value(argument));
+        }
+        rendered.push_str("  )");
+
+        rendered
+    } else {
+        format!(
+            "({})",
+            arguments
+                .iter()
+                .map(render_input_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+fn render_input_value(input_value: &IntrospectionInputValue) -> String {
+    match input_value.default_value.as_ref() {
+        Some(default_value) => format!(
+            "{}: {} = {}",
+            input_value.name, input_value.ty, default_value
+        ),
+        None => format!("{}: {}", input_value.name, input_value.ty),
+    }
+}
+
+fn render_deprecation(is_deprecated: bool, reason: Option<&str>) -> String {
+    match reason {
+        _ if !is_deprecated => String::new(),
+        Some(reason) if reason != DEFAULT_DEPRECATION_REASON => {
+            format!(
+                " @deprecated(reason: {})",
+                serde_json::Value::String(reason.to_string())
+            )
+        }
+        _ => " @deprecated".to_string(),
+    }
+}
+
+fn render_description(
+    rendered: &mut String,
+    description: Option<&str>,
+    indentation: &str,
+    options: &SdlOptions,
+) {
+    let Some(description) = description.filter(|_| !options.no_descriptions) else {
+        return;
+    };
+
+    let description = description.replace(r#"""""#, r#"\""""#);
+    let _ = writeln!(rendered, r#"{indentation}""""#);
+    for line in description.lines() {
+        if line.is_empty() {
+            rendered.push('\n');
+        } else {
+            let _ = writeln!(rendered, "{indentation}{line}");
+        }
+    }
+    let _ = writeln!(rendered, r#"{indentation}""""#);
+}
+
+pub const FEDERATION_SDL_QUERY: &str = "query FederationSdl { _service { sdl } }";
+
+// a custom introspection query replaces the default one, its result still has to be a valid __schema
+pub async fn fetch_introspection(
+    server_endpoint: &str,
+    headers: HeaderMap,
+    introspection_query: Option<&str>,
+) -> Result<IntrospectionSchema, Box<dyn std::error::Error>> {
+    let (query, operation_name) = match introspection_query {
+        Some(introspection_query) => (
+            introspection_query,
+            infer_operation_name(introspection_query),
+        ),
+        None => (INTROSPECTION_QUERY, Some("IntrospectionQuery".to_string())),
+    };
+    let introspection =
+        fetch_schema_response(server_endpoint, headers, query, operation_name.as_deref()).await?;
+
+    IntrospectionSchema::from_json(serde_json::to_value(introspection)?).map_err(|e| {
+        IntrospectionRequestFailed {
+            endpoint: server_endpoint.to_string(),
+            reason: e.to_string(),
+        }
+        .into()
+    })
+}
+
+// federated subgraphs expose their sdl through the _service field
+pub async fn fetch_federation_sdl(
+    server_endpoint: &str,
+    headers: HeaderMap,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let response = fetch_schema_response(
+        server_endpoint,
+        headers,
+        FEDERATION_SDL_QUERY,
+        Some("FederationSdl"),
+    )
+    .await?;
+
+    response
+        .data
+        .as_ref()
+        .and_then(|data| data.pointer("/_service/sdl"))
+        .and_then(|sdl| sdl.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            IntrospectionRequestFailed {
+                endpoint: server_endpoint.to_string(),
+                reason: "the response has no _service.sdl string".to_string(),
+            }
+            .into()
+        })
+}
+
+pub async fn fetch_sdl(
+    server_endpoint: &str,
+    headers: HeaderMap,
+    federation: bool,
+    introspection_query: Option<&str>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if federation {
+        match fetch_federation_sdl(server_endpoint, headers.clone()).await {
+            Ok(sdl) => return Ok(sdl),
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "cannot fetch the federation sdl, falling back to introspection"
+                )
+            }
+        }
+    }
+
+    Ok(
+        fetch_introspection(server_endpoint, headers, introspection_query)
+            .await?
+            .to_sdl(&SdlOptions::default()),
+    )
+}
+
+async fn fetch_schema_response(
+    server_endpoint: &str,
+    mut headers: HeaderMap,
+    query: &str,
+    operation_name: Option<&str>,
+) -> Result<GraphQlResponse, IntrospectionRequestFailed> {
+    headers
+        .entry("accept")
+        .or_insert_with(|| HeaderValue::from_static("application/json"));
+
+    let mut schema_response = None;
+    try_http_request(
+        &reqwest::Client::new(),
+        server_endpoint,
+        headers,
+        Some(query.to_string()),
+        operation_name,
+        serde_json::Map::default(),
+        &mut |response, _metadata| {
+            schema_response = Some(response);
+            Ok(())
+        },
+        &ClientOptions::default(),
+    )
+    .await
+    .map_err(|e| IntrospectionRequestFailed {
+        endpoint: server_endpoint.to_string(),
+        reason: e.to_string(),
+    })?;
+
+    let schema_response = schema_response.ok_or_else(|| IntrospectionRequestFailed {
+        endpoint: server_endpoint.to_string(),
+        reason: "no response".to_string(),
+    })?;
+    if let Some(error) = schema_response.errors.first() {
+        return Err(IntrospectionRequestFailed {
+            endpoint: server_endpoint.to_string(),
+            reason: error
+                .get("message")
+                .and_then(|message| message.as_str())
+                .unwrap_or("unknown error")
+                .to_string(),
+        });
+    }
+
+    Ok(schema_response)
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("IntrospectionRequestFailed: endpoint = '{endpoint}', reason = '{reason}'")]
+    pub struct IntrospectionRequestFailed {
+        pub endpoint: String,
+        pub reason: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidIntrospectionResult: reason = '{reason}'")]
+    pub struct InvalidIntrospectionResult {
+        pub reason: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const INTROSPECTION_RESULT: &str = r#"{
+        "data": {
+            "__schema": {
+                "queryType": { "name": "Query" },
+                "mutationType": null,
+                "subscriptionType": null,
+                "types": [
+                    {
+                        "kind": "OBJECT",
+                        "name": "Query",
+                        "description": null,
+                        "fields": [
+                            {
+                                "name": "user",
+                                "description": null,
+                                "args": [
+                                    {
+                                        "name": "id",
+                                        "description": null,
+                                        "type": {
+                                            "kind": "NON_NULL",
+                                            "name": null,
+                                            "ofType": { "kind": "SCALAR", "name": "ID", "ofType": null }
+                                        },
+                                        "defaultValue": null
+                                    }
+                                ],
+                                "type": { "kind": "OBJECT", "name": "User", "ofType": null },
+                                "isDeprecated": false,
+                                "deprecationReason": null
+                            }
+                        ],
+                        "inputFields": null,
+                        "interfaces": [],
+                        "enumValues": null,
+                        "possibleTypes": null
+                    },
+                    {
+                        "kind": "OBJECT",
+                        "name": "User",
+                        "description": "A user of the service",
+                        "fields": [
+                            {
+                                "name": "name",
+                                "description": null,
+                                "args": [],
+                                "type": { "kind": "SCALAR", "name": "String", "ofType": null },
+                                "isDeprecated": true,
+                                "deprecationReason": "Use fullName"
+                            },
+                            {
+                                "name": "id",
+                                "description": null,
+                                "args": [],
+                                "type": {
+                                    "kind": "NON_NULL",
+                                    "name": null,
+                                    "ofType": { "kind": "SCALAR", "name": "ID", "ofType": null }
+                                },
+                                "isDeprecated": false,
+                                "deprecationReason": null
+                            }
+                        ],
+                        "inputFields": null,
+                        "interfaces": [],
+                        "enumValues": null,
+                        "possibleTypes": null
+                    },
+                    {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "description": null,
+                        "fields": null,
+                        "inputFields": null,
+                        "interfaces": null,
+                        "enumValues": null,
+                        "possibleTypes": null
+                    }
+                ],
+                "directives": []
+            }
+        }
+    }"#;
+
+    fn introspection_result() -> serde_json::Value {
+        serde_json::from_str(INTROSPECTION_RESULT).unwrap()
+    }
+
+    #[test]
+    fn response_data_and_schema_are_accepted() {
+        let response = introspection_result();
+        let data = response["data"].clone();
+        let schema = data["__schema"].clone();
+
+        for value in [response, data, schema] {
+            let schema = IntrospectionSchema::from_json(value).unwrap();
+
+            assert_eq!(schema.types.len(), 3);
+        }
+    }
+
+    #[test]
+    fn data_that_is_not_an_object_is_invalid() {
+        for data in [
+            json!([]),
+            json!("schema"),
+            json!(1),
+            json!(true),
+            json!(null),
+        ] {
+            let result = IntrospectionSchema::from_json(json!({ "data": data }));
+
+            assert!(result.is_err(), "{data}");
+        }
+    }
+
+    #[test]
+    fn data_without_schema_is_invalid() {
+        let result = IntrospectionSchema::from_json(json!({ "data": { "user": null } }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn schema_is_rendered_as_sdl() {
+        let schema = IntrospectionSchema::from_json(introspection_result()).unwrap();
+
+        let sdl = schema.to_sdl(&SdlOptions::default());
+
+        assert_eq!(
+            sdl,
+            r#"type Query {
+  user(id: ID!): User
+}
+
+"""
+A user of the service
+"""
+type User {
+  name: String @deprecated(reason: "Use fullName")
+  id: ID!
+}
+"#
+        );
+    }
+
+    #[test]
+    fn sorted_sdl_has_no_descriptions_with_no_descriptions() {
+        let schema = IntrospectionSchema::from_json(introspection_result()).unwrap();
+
+        let sdl = schema.to_sdl(&SdlOptions {
+            sort: true,
+            include_builtins: true,
+            no_descriptions: true,
+        });
+
+        assert_eq!(
+            sdl,
+            r#"type Query {
+  user(id: ID!): User
+}
+
+scalar String
+
+type User {
+  id: ID!
+  name: String @deprecated(reason: "Use fullName")
+}
+"#
+        );
+    }
+}
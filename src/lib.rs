@@ -1,3 +1,18 @@
+pub mod apollo_tracing;
+pub mod assertions;
+pub mod benchmark;
 pub mod clap_types;
 pub mod client;
+pub mod error_context;
+pub mod file_upload;
+pub mod introspection;
+pub mod lifecycle;
+pub mod masking;
+pub mod metrics;
+pub mod operations;
+pub mod output_template;
+pub mod request_signing;
+pub mod response_schema;
 pub mod schema_diff;
+pub mod tls;
+pub mod trace_context;
@@ -0,0 +1,25 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleEvent {
+    Connecting,
+    Connected,
+    Subscribed,
+    Event,
+    Reconnecting,
+    Completed,
+    Closed,
+    Error,
+}
+
+// one json object per line on stderr, so a supervising process can follow the state of the client
+pub fn print_lifecycle_event(event: LifecycleEvent, details: serde_json::Value) {
+    let mut line = serde_json::json!({
+        "event": event,
+        "timestamp": humantime::format_rfc3339_millis(std::time::SystemTime::now()).to_string(),
+    });
+    if let (Some(line), serde_json::Value::Object(details)) = (line.as_object_mut(), details) {
+        line.extend(details);
+    }
+
+    eprintln!("{line}");
+}
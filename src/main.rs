@@ -1,42 +1,788 @@
 mod cli;
 
-use clap::Parser;
-use cli::Cli;
+use std::io::{IsTerminal, Write};
+
+use clap::CommandFactory;
+use cli::{parse_cli, Cli, Command, OutputFormat};
 use graphql_cli_tools::{
-    client::{execute, load_variables},
-    schema_diff::diff_schema,
+    apollo_tracing::ApolloTracing,
+    assertions::Assertion,
+    benchmark::{run_benchmark, BenchmarkOptions},
+    client::{
+        append_fragment_files, execute_query, load_query, load_query_with_front_matter,
+        load_variable_sets, load_variable_sets_ndjson, normalize_server_endpoint, ping,
+        resolve_imports, resolve_response_references, ClientOptions, Deadline, GraphQlResponse,
+        ResponseEnvelopeKeys, ResponseMetadata, ResumeCursor, VariablesMerge,
+    },
+    error_context::render_error_context,
+    introspection::{fetch_sdl, IntrospectionSchema, SdlOptions},
+    masking::Mask,
+    metrics::{serve_metrics, Metrics},
+    operations::{
+        declared_variable_names, extract_operation, infer_operation_name,
+        infer_operation_name_from_path, is_operation_name_pattern, matching_operation_names,
+        parse_operation_headers, required_variable_names, variable_defaults,
+    },
+    output_template::render_output_template,
+    response_schema::ResponseSchema,
+    schema_diff::{diff_schema, diff_schema_dirs, DiffSchemaOptions, SchemaSource},
+    tls::load_ca_certificates,
+    trace_context::{TraceContext, TraceId},
 };
+use reqwest::header::HeaderMap;
+use sha2::{Digest, Sha256};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
+    let cli = parse_cli()?;
+    init_logger(cli.log_level, cli.log_format);
+
+    match cli.command {
+        Command::Client(params) => {
+            let mut params = *params;
+            // an invalid endpoint is reported before any file is loaded
+            let server_endpoint = normalize_server_endpoint(&params.server_endpoint)?;
+            params.variables.append(&mut params.variable_files);
+            let variables_merge = VariablesMerge {
+                precedence: params.variables_precedence,
+                deep_merge: params.deep_merge,
+            };
+            let mut variable_sets = match params.variables_ndjson {
+                _ if params.no_variables => vec![serde_json::Map::default()],
+                Some(ndjson_path) => {
+                    load_variable_sets_ndjson(ndjson_path, params.variables, variables_merge)?
+                }
+                None => load_variable_sets(
+                    params.variables_from_json,
+                    params.variables,
+                    variables_merge,
+                )?,
+            };
+            // the query file is read once, its front-matter only provides defaults
+            let (query_file, default_variables) = match params.query_path.as_ref() {
+                Some(query_path) => {
+                    let (query, default_variables) = load_query_with_front_matter(query_path)?;
+                    (Some((query_path, query)), default_variables)
+                }
+                None => (None, serde_json::Map::default()),
+            };
+            if !params.no_variables {
+                for variables in variable_sets.iter_mut() {
+                    for (name, value) in default_variables.iter() {
+                        variables
+                            .entry(name.clone())
+                            .or_insert_with(|| value.clone());
+                    }
+                }
+            }
+            // a single cursor file cannot hold the cursors of several subscriptions
+            if params.cursor_file.is_some() && variable_sets.len() > 1 {
+                return Err(CursorFileWithVariableSets {
+                    variable_sets: variable_sets.len(),
+                }
+                .into());
+            }
+            let resume_cursor = params.resume_field.map(|json_path| ResumeCursor {
+                json_path,
+                variable: params.resume_variable,
+                cursor_file: params.cursor_file,
+            });
+            if let Some(resume_cursor) = resume_cursor.as_ref() {
+                if let Some(cursor) = resume_cursor.load_saved_cursor()? {
+                    tracing::info!(%cursor, "resuming from the saved cursor");
+                    for variables in variable_sets.iter_mut() {
+                        variables.insert(resume_cursor.variable.clone(), cursor.clone());
+                    }
+                }
+            }
+            let query = match (params.query.clone(), query_file) {
+                (Some(query), _) => Some(query),
+                (None, Some((query_path, query))) if params.resolve_imports => {
+                    Some(resolve_imports(query_path, query)?)
+                }
+                (None, Some((_query_path, query))) => Some(query),
+                (None, None) => None,
+            };
+            let query = query
+                .map(|query| append_fragment_files(query, &params.fragment_files))
+                .transpose()?;
+            let fan_out = variable_sets.len() > 1;
+            let mut headers = HeaderMap::new();
+            for (header_name, header_value) in params.headers_from_json.into_iter().flatten() {
+                headers.insert(header_name, header_value);
+            }
+            for (header_name, _header_value) in params.headers.iter() {
+                headers.remove(header_name);
+            }
+            headers.extend(params.headers);
+            let metrics = params.metrics_port.map(|metrics_port| {
+                let metrics = std::sync::Arc::new(Metrics::default());
+                let served_metrics = metrics.clone();
+                let metrics_address = std::net::SocketAddr::new(params.metrics_bind, metrics_port);
+                tokio::spawn(async move {
+                    if let Err(e) = serve_metrics(served_metrics, metrics_address).await {
+                        tracing::error!(error = %e, "cannot serve metrics");
+                    }
+                });
+                metrics
+            });
+            let mut tee_file = params
+                .tee
+                .as_ref()
+                .map(|tee_path| {
+                    std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(tee_path)
+                })
+                .transpose()?;
+            let mut pipe_to = params
+                .pipe_to
+                .as_ref()
+                .map(|command| {
+                    std::process::Command::new("sh")
+                        .arg("-c")
+                        .arg(command)
+                        .stdin(std::process::Stdio::piped())
+                        .spawn()
+                })
+                .transpose()?;
+            let mut pipe_to_stdin = pipe_to.as_mut().and_then(|child| child.stdin.take());
+            let assertions = params
+                .assert_equals
+                .iter()
+                .map(|(path, expected)| Assertion::Equals {
+                    path: path.clone(),
+                    expected: expected.clone(),
+                })
+                .chain(
+                    params
+                        .assert_exists
+                        .iter()
+                        .map(|path| Assertion::Exists { path: path.clone() }),
+                )
+                .chain(params.assert_no_errors.then_some(Assertion::NoErrors))
+                .collect::<Vec<_>>();
+            let response_schema = params
+                .response_schema
+                .as_ref()
+                .map(ResponseSchema::load)
+                .transpose()?;
+            let mut failed_assertions = 0;
+            let mut failed_responses = 0;
+            let mut recent_payload_hashes = std::collections::VecDeque::new();
+            let error_context_query = query.as_deref().filter(|_| params.error_context);
+            let trace_context = params.trace_id.map(|trace_id| TraceContext {
+                trace_id: trace_id.unwrap_or_else(TraceId::random),
+                tracestate: params.tracestate,
+            });
+            if let Some(trace_context) = trace_context.as_ref() {
+                tracing::info!(trace_id = %trace_context.trace_id, "trace context");
+            }
 
-    let cli = Cli::parse();
+            let options = ClientOptions {
+                try_reconnect_duration: params
+                    .try_reconnect_duration
+                    .map(|duration| duration.into()),
+                omit_operation_name: params.no_operation_name,
+                resolve_imports: params.resolve_imports,
+                http_method: params.method,
+                request_encoding: params.encoding,
+                http_version: params.http_version,
+                mask: Mask::new(&params.mask),
+                persisted_query_id: params.apq_id,
+                extensions: params.extensions_json.or(params.extensions_from_json),
+                ws_protocol: params.ws_protocol,
+                response_envelope_keys: ResponseEnvelopeKeys {
+                    data: params.data_key,
+                    errors: params.errors_key,
+                },
+                file_upload_dirs: params.file_upload_dirs,
+                auto_transport: params.auto_transport,
+                ws_endpoint: params.ws_endpoint,
+                connect_timeout: params.connect_timeout.map(|duration| duration.into()),
+                request_timeout: params.deadline.map(|duration| duration.into()),
+                deadline: params.deadline.map(|duration| Deadline {
+                    duration: duration.into(),
+                    header: params.deadline_header,
+                    format: params.deadline_format,
+                }),
+                max_response_size: params.max_response_size,
+                error_max_len: params.error_max_len,
+                ws_cookies: params.ws_cookies,
+                cache_ttl: params.cache_ttl.map(|duration| duration.into()),
+                request_jitter: params.request_jitter.map(|duration| duration.into()),
+                ca_certificates: load_ca_certificates(
+                    params.cacert.as_deref(),
+                    params.cacert_from_env,
+                )?,
+                resume_cursor,
+                sort_variables: params.sort_variables,
+                print_handshake: params.print_handshake,
+                expected_operation_kind: params.expect,
+                require_operation_name: params.require_operation_name,
+                fail_fast: params.fail_fast,
+                no_ws_compression: params.no_ws_compression,
+                no_decompress: params.no_decompress,
+                no_conditional: params.no_conditional,
+                emit_lifecycle: params.emit_lifecycle,
+                show_size: params.show_size,
+                ws_passthrough_types: params.ws_passthrough_types,
+                retry_on_graphql_error: params.retry_on_graphql_error,
+                retry_on_graphql_error_max: params.retry_on_graphql_error_max,
+                retry_on_graphql_error_backoff: params.retry_on_graphql_error_backoff.into(),
+                strict: params.strict,
+                ws_subscription_id: params.ws_subscription_id,
+                metrics,
+                header_commands: params.header_commands,
+                trace_context,
+                hmac_signer: params.hmac_signer,
+            };
+            let operation_name = params.operation_name.or_else(|| {
+                let query = query.as_deref().filter(|_| params.infer_operation)?;
+                let query_path = params.query_path.as_deref()?;
 
-    match cli {
-        Cli::Client(params) => {
-            let variables = load_variables(params.variables_from_json, params.variables)?;
-            let headers = params.headers.into_iter().collect();
+                infer_operation_name_from_path(query, query_path)
+            });
+            let (operation_names, label) = match operation_name {
+                Some(pattern) if is_operation_name_pattern(&pattern) => {
+                    let query = query.as_deref().ok_or(OperationPatternWithoutQuery)?;
+                    let operation_names = matching_operation_names(query, &pattern)?;
 
-            execute(
-                params.server_endpoint,
-                headers,
-                params.query_path,
-                params.operation_name,
-                variables,
-                |response| {
-                    println!("{}", serde_json::to_string_pretty(&response)?);
+                    (operation_names.into_iter().map(Some).collect(), true)
+                }
+                operation_name => (vec![operation_name], params.label),
+            };
+
+            let mut process_response = |response: GraphQlResponse, metadata: &ResponseMetadata| {
+                if let Some(raw_body) = metadata.raw_body.as_deref() {
+                    let raw_body =
+                        &raw_body[..params.head.unwrap_or(usize::MAX).min(raw_body.len())];
+                    return write_output(
+                        raw_body,
+                        &mut pipe_to_stdin,
+                        pipe_to.is_none(),
+                        &mut tee_file,
+                    );
+                }
+
+                if params.dedup {
+                    let payload_hash = sha256_hex(serde_json::to_string(&response)?.as_bytes());
+                    if recent_payload_hashes.contains(&payload_hash) {
+                        tracing::debug!("duplicate payload suppressed");
+                        return Ok(());
+                    }
+
+                    recent_payload_hashes.push_back(payload_hash);
+                    if recent_payload_hashes.len() > params.dedup_window as usize {
+                        recent_payload_hashes.pop_front();
+                    }
+                }
+
+                if params.show_tracing {
+                    match ApolloTracing::from_extensions(&response.extensions) {
+                        Some(Ok(tracing)) => eprint!("{}", tracing.render_table()),
+                        Some(Err(e)) => {
+                            tracing::warn!(error = %e, "cannot parse tracing extension")
+                        }
+                        None => (),
+                    }
+                }
+
+                if let Some(query) = error_context_query {
+                    for error in response.errors.iter() {
+                        eprint!("{}", render_error_context(query, error));
+                    }
+                }
+
+                if !response.errors.is_empty() {
+                    let is_partial = response.data.as_ref().is_some_and(|data| !data.is_null());
+                    if is_partial {
+                        tracing::warn!(
+                            errors = response.errors.len(),
+                            "partial response, data is returned with errors"
+                        );
+                    }
+
+                    if params.flatten_errors {
+                        for (index, error) in response.errors.iter().enumerate() {
+                            eprintln!(
+                                "{}",
+                                serde_json::json!({
+                                    "operation": metadata.operation_name,
+                                    "index": index,
+                                    "error": error,
+                                })
+                            );
+                        }
+                    }
+
+                    if params.fail_on_error {
+                        if !params.flatten_errors {
+                            for error in response.errors.iter() {
+                                eprintln!("{}", serde_json::to_string(error)?);
+                            }
+                        }
+
+                        if !(is_partial && params.partial_ok) {
+                            failed_responses += 1;
+                        }
+                    }
+                }
+
+                let mut output = serde_json::to_value(&response)?;
+
+                for assertion in assertions.iter() {
+                    if assertion.check(&output) {
+                        eprintln!("assertion passed: {assertion}");
+                    } else {
+                        eprintln!("assertion failed: {assertion}");
+                        failed_assertions += 1;
+                    }
+                }
+
+                if let Some(response_schema) = response_schema.as_ref() {
+                    let data = response.data.clone().unwrap_or_default();
+                    let violations = response_schema.violations(&data);
+                    for violation in violations.iter() {
+                        eprintln!("schema violation: {violation}");
+                    }
+                    if !violations.is_empty() {
+                        failed_responses += 1;
+                    }
+                }
+
+                let rendered_template = params
+                    .output_template
+                    .as_deref()
+                    .map(|output_template| {
+                        render_output_template(output_template, &output, params.strict_template)
+                    })
+                    .transpose()?;
+
+                if let Some(subscription_id) = metadata.subscription_id.as_ref().filter(|_| fan_out)
+                {
+                    output = serde_json::json!({
+                        "id": subscription_id,
+                        "payload": output,
+                    });
+                }
+
+                if label {
+                    output = serde_json::json!({
+                        "operation": metadata.operation_name,
+                        "response": output,
+                    });
+                }
+
+                if params.with_request {
+                    output = serde_json::json!({
+                        "request": {
+                            "endpoint": server_endpoint,
+                            "operationName": metadata.operation_name,
+                            "variablesSha256": sha256_hex(
+                                serde_json::to_string(&metadata.variables)?.as_bytes()
+                            ),
+                            "timestamp": humantime::format_rfc3339_millis(
+                                std::time::SystemTime::now()
+                            )
+                            .to_string(),
+                        },
+                        "response": output,
+                    });
+                }
+
+                if let Some(save_response_path) = params.save_response.as_ref() {
+                    std::fs::write(save_response_path, serde_json::to_string_pretty(&response)?)?;
+                }
+
+                let mut output = match rendered_template {
+                    Some(rendered_template) => rendered_template,
+                    None => serde_json::to_string_pretty(&output)?,
+                };
+                if let Some(head) = params.head {
+                    truncate_output(&mut output, head);
+                }
+                output.push('\n');
+                write_output(
+                    output.as_bytes(),
+                    &mut pipe_to_stdin,
+                    pipe_to.is_none(),
+                    &mut tee_file,
+                )?;
+
+                if params.summary {
+                    if let Some(status) = metadata.status {
+                        eprintln!(
+                            "status = {}, data keys = {}, errors = {}, elapsed = {:?}",
+                            status,
+                            response
+                                .data
+                                .as_ref()
+                                .and_then(|data| data.as_object())
+                                .map_or(0, |data| data.len()),
+                            response.errors.len(),
+                            metadata.elapsed.unwrap_or_default(),
+                        );
+                    }
+                }
+
+                Ok(())
+            };
+
+            for operation_name in operation_names {
+                let mut variable_sets = variable_sets.clone();
+                for variables in variable_sets.iter_mut() {
+                    resolve_response_references(variables, params.save_response.as_deref())?;
+                }
+
+                if let Some(query) = query.as_deref().filter(|_| params.no_variables) {
+                    let required_variable_names =
+                        required_variable_names(query, operation_name.as_deref())?
+                            .unwrap_or_default();
+
+                    if !required_variable_names.is_empty() {
+                        return Err(RequiredVariablesWithNoVariables {
+                            operation_name: operation_name
+                                .clone()
+                                .or_else(|| infer_operation_name(query))
+                                .unwrap_or_else(|| "<anonymous>".to_string()),
+                            variable_names: required_variable_names.join(", "),
+                        }
+                        .into());
+                    }
+                }
+
+                if let Some(query) = query.as_deref().filter(|_| params.check_variables) {
+                    let has_variables = variable_sets.iter().any(|variables| !variables.is_empty());
+                    let declared_variable_names =
+                        declared_variable_names(query, operation_name.as_deref())?;
+
+                    if has_variables
+                        && declared_variable_names.is_some_and(|names| names.is_empty())
+                    {
+                        let operation_name = operation_name
+                            .clone()
+                            .or_else(|| infer_operation_name(query))
+                            .unwrap_or_else(|| "<anonymous>".to_string());
+                        if params.strict {
+                            return Err(UndeclaredVariables { operation_name }.into());
+                        }
+
+                        eprintln!(
+                            "variables are given, but operation '{operation_name}' declares none, they are ignored by the server"
+                        );
+                    }
+                }
+
+                if let Some(query) = query.as_deref().filter(|_| params.apply_defaults) {
+                    let default_variables = variable_defaults(query, operation_name.as_deref())?;
+
+                    for variables in variable_sets.iter_mut() {
+                        for (name, value) in default_variables.iter() {
+                            variables
+                                .entry(name.clone())
+                                .or_insert_with(|| value.clone());
+                        }
+                    }
+                }
+
+                execute_query(
+                    server_endpoint.as_str(),
+                    headers.clone(),
+                    query.clone(),
+                    operation_name,
+                    variable_sets,
+                    &mut process_response,
+                    &options,
+                )
+                .await?;
+            }
+
+            // closing stdin signals the end of the responses to the command
+            drop(pipe_to_stdin);
+            if let Some(mut pipe_to) = pipe_to {
+                let status = pipe_to.wait()?;
+                if !status.success() {
+                    tracing::warn!(%status, "the --pipe-to command failed");
+                }
+            }
+
+            if failed_assertions > 0 || failed_responses > 0 {
+                std::process::exit(1);
+            }
+
+            Ok(())
+        }
+        Command::DiffSchema(params) => {
+            let options = DiffSchemaOptions::from(params.diff_options);
+
+            let has_breaking_change = if params.dirs {
+                let (SchemaSource::Path(left_dir), SchemaSource::Path(right_dir)) =
+                    (params.schema_source_left, params.schema_source_right)
+                else {
+                    return Err(GitSourceWithDirs.into());
+                };
+                diff_schema_dirs(left_dir, right_dir, &options)?
+            } else {
+                diff_schema(
+                    params.schema_source_left,
+                    params.schema_source_right,
+                    &options,
+                )?
+            };
+            if has_breaking_change {
+                std::process::exit(1);
+            }
+
+            Ok(())
+        }
+        Command::DiffEndpoints(params) => {
+            let introspection_query = params
+                .introspection_query
+                .as_ref()
+                .map(std::fs::read_to_string)
+                .transpose()?;
+            let (schema_left, schema_right) = tokio::try_join!(
+                fetch_sdl(
+                    &params.left_endpoint,
+                    params.left_headers.into_iter().collect(),
+                    params.federation,
+                    introspection_query.as_deref(),
+                ),
+                fetch_sdl(
+                    &params.right_endpoint,
+                    params.right_headers.into_iter().collect(),
+                    params.federation,
+                    introspection_query.as_deref(),
+                ),
+            )?;
+
+            let has_breaking_change =
+                diff_schema(schema_left, schema_right, &params.diff_options.into())?;
+            if has_breaking_change {
+                std::process::exit(1);
+            }
+
+            Ok(())
+        }
+        Command::ExtractOperation(params) => {
+            let query = load_query(params.query_path)?;
+            let operation = extract_operation(&query, &params.operation_name)?;
+
+            match params.output {
+                Some(output_path) => std::fs::write(output_path, operation)?,
+                None => print!("{operation}"),
+            }
 
-                    Ok(())
+            Ok(())
+        }
+        Command::Completions(params) => {
+            let mut command = Cli::command();
+            let bin_name = command.get_name().to_string();
+            clap_complete::generate(params.shell, &mut command, bin_name, &mut std::io::stdout());
+
+            Ok(())
+        }
+        Command::ListOperations(params) => {
+            let query = load_query(params.query_path)?;
+            let operations = parse_operation_headers(&query)?;
+
+            match params.format {
+                OutputFormat::Text => {
+                    for operation in operations {
+                        println!(
+                            "{} {}",
+                            operation.kind,
+                            operation.name.as_deref().unwrap_or("<anonymous>"),
+                        );
+                    }
+                }
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&operations)?);
+                }
+            }
+
+            Ok(())
+        }
+        Command::IntrospectionToSdl(params) => {
+            let introspection =
+                serde_json::from_str(&std::fs::read_to_string(params.introspection_path)?)?;
+
+            print!(
+                "{}",
+                IntrospectionSchema::from_json(introspection)?.to_sdl(&SdlOptions {
+                    sort: params.sort,
+                    include_builtins: params.include_builtins,
+                    no_descriptions: params.no_descriptions,
+                })
+            );
+
+            Ok(())
+        }
+        Command::ExportCompletions(params) => {
+            let introspection =
+                serde_json::from_str(&std::fs::read_to_string(params.introspection_path)?)?;
+            let completion_items =
+                IntrospectionSchema::from_json(introspection)?.completion_items(params.root);
+
+            println!("{}", serde_json::to_string_pretty(&completion_items)?);
+
+            Ok(())
+        }
+        Command::Benchmark(params) => {
+            let query = load_query(params.query_path)?;
+            let report = run_benchmark(
+                &params.server_endpoint,
+                params.headers.into_iter().collect(),
+                query,
+                params.operation_name,
+                params.variables.into_iter().collect(),
+                &BenchmarkOptions {
+                    requests: params.requests as usize,
+                    concurrency: params.concurrency as usize,
                 },
-                params
-                    .try_reconnect_duration
-                    .map(|duration| duration.into()),
             )
-            .await
+            .await?;
+
+            match params.format {
+                OutputFormat::Text => print!("{}", report.render_table()),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+
+            Ok(())
+        }
+        Command::Ping(params) => {
+            let headers: HeaderMap = params.headers.into_iter().collect();
+            let client = reqwest::Client::new();
+            let options = ClientOptions::default();
+
+            let mut succeeded = 0;
+            for index in 0..params.repeat {
+                if index > 0 {
+                    tokio::time::sleep(params.interval.into()).await;
+                }
+
+                match ping(&client, &params.server_endpoint, headers.clone(), &options).await {
+                    Ok(latency) => {
+                        succeeded += 1;
+                        println!("reachable, latency = {latency:?}");
+                    }
+                    Err(e) => println!("unreachable, reason = {e}"),
+                }
+            }
+
+            if params.repeat > 1 {
+                println!(
+                    "succeeded = {succeeded}/{}, success rate = {:.1}%",
+                    params.repeat,
+                    f64::from(succeeded) * 100.0 / f64::from(params.repeat),
+                );
+            }
+
+            if succeeded < params.repeat {
+                std::process::exit(1);
+            }
+
+            Ok(())
         }
-        Cli::DiffSchema(params) => {
-            diff_schema(params.schema_source_left, params.schema_source_right)
+    }
+}
+
+// the output goes to the --pipe-to command or to stdout, and to the --tee file
+fn write_output(
+    output: &[u8],
+    pipe_to_stdin: &mut Option<std::process::ChildStdin>,
+    to_stdout: bool,
+    tee_file: &mut Option<std::fs::File>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(stdin) = pipe_to_stdin.as_mut() {
+        // the command may stop reading early (e.g. `head`), the rest of the responses are dropped then
+        if let Err(e) = stdin.write_all(output) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+            tracing::warn!(
+                "the --pipe-to command stopped reading, the remaining responses are discarded"
+            );
+            *pipe_to_stdin = None;
         }
+    } else if to_stdout {
+        std::io::stdout().write_all(output)?;
+    }
+    if let Some(tee_file) = tee_file.as_mut() {
+        tee_file.write_all(output)?;
+    }
+
+    Ok(())
+}
+
+// the cut never splits a multibyte character, so the kept part can be shorter than the limit
+fn truncate_output(output: &mut String, max_bytes: usize) {
+    if output.len() <= max_bytes {
+        return;
     }
+
+    let cut_at = (0..=max_bytes)
+        .rev()
+        .find(|index| output.is_char_boundary(*index))
+        .unwrap_or_default();
+    output.truncate(cut_at);
+    output.push('…');
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+// --log-level overrides RUST_LOG, only the errors are logged without them
+fn init_logger(log_level: Option<LevelFilter>, log_format: OutputFormat) {
+    let env_filter = match log_level {
+        Some(log_level) => EnvFilter::default().add_directive(log_level.into()),
+        None => EnvFilter::builder()
+            .with_default_directive(LevelFilter::ERROR.into())
+            .from_env_lossy(),
+    };
+    // the log records of the dependencies, e.g. reqwest, are forwarded to the subscriber by init
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr);
+
+    match log_format {
+        OutputFormat::Text => builder.init(),
+        OutputFormat::Json => builder.json().flatten_event(true).init(),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("OperationPatternWithoutQuery: an operation name pattern needs a query file")]
+struct OperationPatternWithoutQuery;
+
+#[derive(Debug, thiserror::Error)]
+#[error("CursorFileWithVariableSets: variable sets = {variable_sets}, --cursor-file holds the cursor of a single subscription")]
+struct CursorFileWithVariableSets {
+    variable_sets: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("GitSourceWithDirs: --dirs expects two directories, a git:<revision>:<path> source is a single file")]
+struct GitSourceWithDirs;
+
+#[derive(Debug, thiserror::Error)]
+#[error("UndeclaredVariables: operation = '{operation_name}', variables are given but the operation declares none")]
+struct UndeclaredVariables {
+    operation_name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("RequiredVariablesWithNoVariables: operation = '{operation_name}', variables = '{variable_names}', the operation has required variables but --no-variables is given")]
+struct RequiredVariablesWithNoVariables {
+    operation_name: String,
+    variable_names: String,
 }
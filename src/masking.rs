@@ -0,0 +1,85 @@
+use reqwest::header::HeaderMap;
+
+const MASKED_VALUE: &str = "***";
+const DEFAULT_MASKED_NAMES: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+// the names are compared case insensitively, so the same name masks a header and a variable
+#[derive(Debug, Clone)]
+pub struct Mask {
+    names: Vec<String>,
+}
+
+impl Default for Mask {
+    fn default() -> Self {
+        Self::new(std::iter::empty::<String>())
+    }
+}
+
+impl Mask {
+    // the given names extend the defaults, the defaults cannot be unmasked
+    pub fn new(names: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let mut names = DEFAULT_MASKED_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain(
+                names
+                    .into_iter()
+                    .map(|name| name.as_ref().to_ascii_lowercase()),
+            )
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        Self { names }
+    }
+
+    pub fn is_masked(&self, name: &str) -> bool {
+        self.names
+            .iter()
+            .any(|masked_name| masked_name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn headers(&self, headers: &HeaderMap) -> serde_json::Value {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_masked(name.as_str()) {
+                    MASKED_VALUE.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+
+                (name.to_string(), serde_json::Value::String(value))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    // the fields of the input objects are masked too, e.g. a password in a login input
+    pub fn variables(
+        &self,
+        variables: &serde_json::Map<String, serde_json::Value>,
+    ) -> serde_json::Value {
+        variables
+            .iter()
+            .map(|(name, value)| (name.clone(), self.value(name, value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    fn value(&self, name: &str, value: &serde_json::Value) -> serde_json::Value {
+        if self.is_masked(name) {
+            return MASKED_VALUE.into();
+        }
+
+        match value {
+            serde_json::Value::Object(fields) => self.variables(fields),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(|item| self.value("", item))
+                .collect::<Vec<_>>()
+                .into(),
+            value => value.clone(),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub requests_sent: AtomicU64,
+    pub errors: AtomicU64,
+    pub events_received: AtomicU64,
+    pub reconnects: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record_request_sent(&self) {
+        self.requests_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_event_received(&self) {
+        self.events_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let mut rendered = String::new();
+
+        for (name, help, counter) in [
+            (
+                "graphql_cli_requests_sent_total",
+                "Number of sent requests and subscribe messages",
+                &self.requests_sent,
+            ),
+            (
+                "graphql_cli_errors_total",
+                "Number of failed attempts",
+                &self.errors,
+            ),
+            (
+                "graphql_cli_events_received_total",
+                "Number of received subscription events",
+                &self.events_received,
+            ),
+            (
+                "graphql_cli_reconnects_total",
+                "Number of reconnection attempts",
+                &self.reconnects,
+            ),
+        ] {
+            let _ = writeln!(rendered, "# HELP {name} {help}");
+            let _ = writeln!(rendered, "# TYPE {name} counter");
+            let _ = writeln!(rendered, "{name} {}", counter.load(Ordering::Relaxed));
+        }
+
+        rendered
+    }
+}
+
+pub async fn serve_metrics(
+    metrics: Arc<Metrics>,
+    address: SocketAddr,
+) -> Result<(), std::io::Error> {
+    let listener = TcpListener::bind(address).await?;
+
+    loop {
+        let (mut stream, _address) = listener.accept().await?;
+        let metrics = metrics.clone();
+
+        tokio::spawn(async move {
+            // the request itself is irrelevant, every path serves the metrics
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+
+            let body = metrics.render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n\
+                 {body}",
+                body.len(),
+            );
+
+            if let Err(e) = stream.write_all(response.as_bytes()).await {
+                tracing::warn!(error = %e, "cannot serve metrics");
+            }
+        });
+    }
+}
@@ -0,0 +1,477 @@
+use std::{collections::BTreeSet, path::Path};
+
+use async_graphql_parser::{
+    parse_query,
+    types::{ExecutableDocument, OperationDefinition, OperationType, Selection, SelectionSet},
+    Pos,
+};
+use error::{NoMatchingOperation, UnknownFragment, UnknownOperation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+impl From<OperationType> for OperationKind {
+    fn from(value: OperationType) -> Self {
+        match value {
+            OperationType::Query => OperationKind::Query,
+            OperationType::Mutation => OperationKind::Mutation,
+            OperationType::Subscription => OperationKind::Subscription,
+        }
+    }
+}
+
+impl std::fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationKind::Query => write!(f, "query"),
+            OperationKind::Mutation => write!(f, "mutation"),
+            OperationKind::Subscription => write!(f, "subscription"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OperationHeader {
+    pub name: Option<String>,
+    pub kind: OperationKind,
+}
+
+pub fn parse_operation_headers(
+    query: &str,
+) -> Result<Vec<OperationHeader>, async_graphql_parser::Error> {
+    let document = parse_query(query)?;
+
+    Ok(operation_headers(&document))
+}
+
+pub fn operation_headers(document: &ExecutableDocument) -> Vec<OperationHeader> {
+    // the named operations are stored in a hashmap, so the document order is restored by position
+    let mut operations = document.operations.iter().collect::<Vec<_>>();
+    operations.sort_by_key(|(_name, operation)| operation.pos);
+
+    operations
+        .into_iter()
+        .map(|(name, operation)| OperationHeader {
+            name: name.map(|name| name.to_string()),
+            kind: operation.node.ty.into(),
+        })
+        .collect()
+}
+
+pub fn infer_operation_name(query: &str) -> Option<String> {
+    match parse_operation_headers(query).ok()?.as_slice() {
+        [operation] => operation.name.clone(),
+        _ => None,
+    }
+}
+
+// one operation per file is a common convention, e.g., GetUser.graphql containing GetUser
+pub fn infer_operation_name_from_path(query: &str, query_path: &Path) -> Option<String> {
+    let file_stem = query_path.file_stem()?.to_str()?;
+    let operations = parse_operation_headers(query).ok()?;
+
+    let mut matching_operations = operations
+        .into_iter()
+        .filter(|operation| operation.name.as_deref() == Some(file_stem));
+    match (matching_operations.next(), matching_operations.next()) {
+        (Some(operation), None) => operation.name,
+        _ => None,
+    }
+}
+
+pub fn find_operation_kind(query: &str, operation_name: Option<&str>) -> Option<OperationKind> {
+    let operations = parse_operation_headers(query).ok()?;
+
+    match operation_name {
+        Some(operation_name) => operations
+            .into_iter()
+            .find(|operation| operation.name.as_deref() == Some(operation_name))
+            .map(|operation| operation.kind),
+        None => match operations.as_slice() {
+            [operation] => Some(operation.kind),
+            _ => None,
+        },
+    }
+}
+
+pub fn variable_defaults(
+    query: &str,
+    operation_name: Option<&str>,
+) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn std::error::Error>> {
+    let document = parse_query(query)?;
+    let Some(operation) = select_operation(&document, operation_name) else {
+        return Ok(serde_json::Map::default());
+    };
+
+    operation
+        .variable_definitions
+        .iter()
+        .filter_map(|variable_definition| {
+            let default_value = variable_definition.node.default_value.as_ref()?;
+
+            Some(
+                default_value
+                    .node
+                    .clone()
+                    .into_json()
+                    .map(|value| (variable_definition.node.name.node.to_string(), value))
+                    .map_err(|e| e.into()),
+            )
+        })
+        .collect()
+}
+
+// none if the operation cannot be selected, so the caller cannot tell anything about its variables
+pub fn declared_variable_names(
+    query: &str,
+    operation_name: Option<&str>,
+) -> Result<Option<Vec<String>>, async_graphql_parser::Error> {
+    let document = parse_query(query)?;
+
+    Ok(
+        select_operation(&document, operation_name).map(|operation| {
+            operation
+                .variable_definitions
+                .iter()
+                .map(|variable_definition| variable_definition.node.name.node.to_string())
+                .collect()
+        }),
+    )
+}
+
+// the non-null variables without a default value, none if the operation cannot be selected
+pub fn required_variable_names(
+    query: &str,
+    operation_name: Option<&str>,
+) -> Result<Option<Vec<String>>, async_graphql_parser::Error> {
+    let document = parse_query(query)?;
+
+    Ok(
+        select_operation(&document, operation_name).map(|operation| {
+            operation
+                .variable_definitions
+                .iter()
+                .filter(|variable_definition| {
+                    !variable_definition.node.var_type.node.nullable
+                        && variable_definition.node.default_value.is_none()
+                })
+                .map(|variable_definition| variable_definition.node.name.node.to_string())
+                .collect()
+        }),
+    )
+}
+
+fn select_operation<'a>(
+    document: &'a ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<&'a OperationDefinition> {
+    let mut operations = document.operations.iter();
+
+    let (_name, operation) = match operation_name {
+        Some(operation_name) => operations
+            .find(|(name, _operation)| name.map(|name| name.as_str()) == Some(operation_name)),
+        // without an operation name only a single operation can be executed
+        None => operations
+            .next()
+            .filter(|_| document.operations.iter().count() == 1),
+    }?;
+
+    Some(&operation.node)
+}
+
+// the operation and the fragments it references, in document order
+pub fn extract_operation(
+    query: &str,
+    operation_name: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let document = parse_query(query)?;
+
+    let (_name, operation) = document
+        .operations
+        .iter()
+        .find(|(name, _operation)| name.map(|name| name.as_str()) == Some(operation_name))
+        .ok_or_else(|| UnknownOperation {
+            name: operation_name.to_string(),
+        })?;
+
+    let mut fragment_names = BTreeSet::new();
+    let mut pending = Vec::new();
+    collect_fragment_spreads(&operation.node.selection_set.node, &mut pending);
+    while let Some(fragment_name) = pending.pop() {
+        if !fragment_names.insert(fragment_name.clone()) {
+            continue;
+        }
+
+        let fragment = document
+            .fragments
+            .get(fragment_name.as_str())
+            .ok_or_else(|| UnknownFragment {
+                name: fragment_name.clone(),
+            })?;
+        collect_fragment_spreads(&fragment.node.selection_set.node, &mut pending);
+    }
+
+    // a definition spans the text up to the start of the next definition
+    let mut definition_starts = document
+        .operations
+        .iter()
+        .map(|(_name, operation)| source_offset(query, operation.pos))
+        .chain(
+            document
+                .fragments
+                .values()
+                .map(|fragment| source_offset(query, fragment.pos)),
+        )
+        .collect::<Vec<_>>();
+    definition_starts.sort();
+    let definition_text = |pos: Pos| {
+        let start = source_offset(query, pos);
+        let end = definition_starts
+            .iter()
+            .find(|definition_start| **definition_start > start)
+            .copied()
+            .unwrap_or(query.len());
+
+        query[start..end].trim_end()
+    };
+
+    let mut fragments = fragment_names
+        .iter()
+        .filter_map(|fragment_name| document.fragments.get(fragment_name.as_str()))
+        .collect::<Vec<_>>();
+    fragments.sort_by_key(|fragment| fragment.pos);
+
+    let mut definitions = vec![definition_text(operation.pos)];
+    definitions.extend(
+        fragments
+            .into_iter()
+            .map(|fragment| definition_text(fragment.pos)),
+    );
+
+    Ok(format!("{}\n", definitions.join("\n\n")))
+}
+
+fn collect_fragment_spreads(selection_set: &SelectionSet, fragment_names: &mut Vec<String>) {
+    for selection in selection_set.items.iter() {
+        match &selection.node {
+            Selection::Field(field) => {
+                collect_fragment_spreads(&field.node.selection_set.node, fragment_names)
+            }
+            Selection::FragmentSpread(fragment_spread) => {
+                fragment_names.push(fragment_spread.node.fragment_name.node.to_string())
+            }
+            Selection::InlineFragment(inline_fragment) => {
+                collect_fragment_spreads(&inline_fragment.node.selection_set.node, fragment_names)
+            }
+        }
+    }
+}
+
+fn source_offset(source: &str, pos: Pos) -> usize {
+    let line_start = source
+        .split_inclusive('\n')
+        .take(pos.line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+
+    source[line_start..]
+        .char_indices()
+        .nth(pos.column.saturating_sub(1))
+        .map_or(source.len(), |(offset, _char)| line_start + offset)
+}
+
+pub fn is_operation_name_pattern(operation_name: &str) -> bool {
+    operation_name.contains(['*', '?'])
+}
+
+pub fn matching_operation_names(
+    query: &str,
+    pattern: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let operation_names = parse_operation_headers(query)?
+        .into_iter()
+        .filter_map(|operation| operation.name)
+        .filter(|name| glob_match(pattern, name))
+        .collect::<Vec<_>>();
+
+    if operation_names.is_empty() {
+        return Err(NoMatchingOperation {
+            pattern: pattern.to_string(),
+        }
+        .into());
+    }
+
+    Ok(operation_names)
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    // the position of the last '*' and the name position it is currently matched up to
+    let mut backtrack = None;
+    let (mut pattern_pos, mut name_pos) = (0, 0);
+
+    while name_pos < name.len() {
+        match pattern.get(pattern_pos) {
+            Some('*') => {
+                backtrack = Some((pattern_pos, name_pos));
+                pattern_pos += 1;
+            }
+            Some(c) if *c == '?' || *c == name[name_pos] => {
+                pattern_pos += 1;
+                name_pos += 1;
+            }
+            _ => match backtrack {
+                Some((star_pos, star_name_pos)) => {
+                    backtrack = Some((star_pos, star_name_pos + 1));
+                    pattern_pos = star_pos + 1;
+                    name_pos = star_name_pos + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pattern_pos..].iter().all(|c| *c == '*')
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("NoMatchingOperation: pattern = '{pattern}'")]
+    pub struct NoMatchingOperation {
+        pub pattern: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UnknownOperation: name = '{name}'")]
+    pub struct UnknownOperation {
+        pub name: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UnknownFragment: name = '{name}'")]
+    pub struct UnknownFragment {
+        pub name: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"query GetUser {
+  user {
+    ...UserFields
+  }
+}
+
+query GetUsers {
+  users {
+    id
+  }
+}
+
+fragment UserFields on User {
+  id
+  role {
+    ...RoleFields
+  }
+}
+
+fragment Unused on User {
+  name
+}
+
+fragment RoleFields on Role {
+  name
+}
+
+mutation DeleteUser {
+  deleteUser
+}
+"#;
+
+    #[test]
+    fn operation_is_extracted_with_the_transitive_fragments() {
+        let operation = extract_operation(DOCUMENT, "GetUser").unwrap();
+
+        assert_eq!(
+            operation,
+            "query GetUser {\n  user {\n    ...UserFields\n  }\n}\n\n\
+             fragment UserFields on User {\n  id\n  role {\n    ...RoleFields\n  }\n}\n\n\
+             fragment RoleFields on Role {\n  name\n}\n"
+        );
+    }
+
+    #[test]
+    fn cyclic_fragments_are_extracted_once() {
+        let document = "query GetUser { user { ...A } }\n\n\
+                        fragment A on User { friend { ...B } }\n\n\
+                        fragment B on User { friend { ...A } }\n\n\
+                        query GetFriends { friends { ...B } }\n";
+
+        let operation = extract_operation(document, "GetUser").unwrap();
+
+        assert_eq!(
+            operation,
+            "query GetUser { user { ...A } }\n\n\
+             fragment A on User { friend { ...B } }\n\n\
+             fragment B on User { friend { ...A } }\n"
+        );
+    }
+
+    #[test]
+    fn unknown_fragment_is_reported() {
+        let e = extract_operation("query GetUser { user { ...Missing } }", "GetUser").unwrap_err();
+
+        assert!(matches!(
+            e.downcast_ref::<UnknownFragment>(),
+            Some(UnknownFragment { name }) if name == "Missing"
+        ));
+    }
+
+    #[test]
+    fn unknown_operation_is_reported() {
+        let e = extract_operation(DOCUMENT, "GetRoles").unwrap_err();
+
+        assert!(e.downcast_ref::<UnknownOperation>().is_some());
+    }
+
+    #[test]
+    fn star_matches_any_suffix() {
+        assert!(glob_match("Get*", "GetUser"));
+        assert!(glob_match("Get*", "Get"));
+        assert!(glob_match("*User*", "GetUsers"));
+        assert!(!glob_match("Get*", "DeleteUser"));
+    }
+
+    #[test]
+    fn question_mark_matches_a_single_character() {
+        assert!(glob_match("GetUser?", "GetUsers"));
+        assert!(!glob_match("GetUser?", "GetUser"));
+        assert!(!glob_match("Get?", "GetUser"));
+    }
+
+    #[test]
+    fn operation_names_are_matched_in_document_order() {
+        let operation_names = matching_operation_names(DOCUMENT, "Get*").unwrap();
+
+        assert_eq!(operation_names, vec!["GetUser", "GetUsers"]);
+    }
+
+    #[test]
+    fn pattern_without_a_match_is_reported() {
+        let e = matching_operation_names(DOCUMENT, "Update*").unwrap_err();
+
+        assert!(matches!(
+            e.downcast_ref::<NoMatchingOperation>(),
+            Some(NoMatchingOperation { pattern }) if pattern == "Update*"
+        ));
+    }
+}
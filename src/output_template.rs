@@ -0,0 +1,67 @@
+use error::{MissingTemplatePath, UnclosedTemplatePlaceholder};
+
+use crate::assertions::select;
+
+// replaces every {jsonpath} placeholder with the selected value, '{{' and '}}' are literal braces
+pub fn render_output_template(
+    template: &str,
+    value: &serde_json::Value,
+    strict: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(brace_pos) = rest.find(['{', '}']) {
+        let (literal, tail) = rest.split_at(brace_pos);
+        rendered.push_str(literal);
+
+        if let Some(tail) = tail.strip_prefix("{{") {
+            rendered.push('{');
+            rest = tail;
+        } else if let Some(tail) = tail.strip_prefix("}}") {
+            rendered.push('}');
+            rest = tail;
+        } else if let Some(tail) = tail.strip_prefix('{') {
+            let (path, tail) = tail
+                .split_once('}')
+                .ok_or_else(|| UnclosedTemplatePlaceholder {
+                    template: template.to_string(),
+                })?;
+
+            match select(value, path.trim()) {
+                Some(serde_json::Value::String(selected)) => rendered.push_str(selected),
+                Some(selected) => rendered.push_str(&selected.to_string()),
+                None if strict => {
+                    return Err(MissingTemplatePath {
+                        path: path.to_string(),
+                    }
+                    .into())
+                }
+                None => (),
+            }
+
+            rest = tail;
+        } else {
+            // a lone closing brace is kept as it is
+            rendered.push('}');
+            rest = &tail[1..];
+        }
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("MissingTemplatePath: path = '{path}'")]
+    pub struct MissingTemplatePath {
+        pub path: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UnclosedTemplatePlaceholder: template = '{template}'")]
+    pub struct UnclosedTemplatePlaceholder {
+        pub template: String,
+    }
+}
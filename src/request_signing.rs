@@ -0,0 +1,113 @@
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue};
+use sha2::Sha256;
+
+#[derive(Clone)]
+pub struct HmacSigner {
+    pub header: HeaderName,
+    pub secret: Vec<u8>,
+}
+
+// the secret is never printed, not even in the debug logs
+impl std::fmt::Debug for HmacSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HmacSigner")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HmacSigner {
+    // the signature is the lowercase hex encoded HMAC-SHA256 of the exact bytes that are sent
+    pub fn sign(&self, body: &[u8]) -> HeaderValue {
+        let signature = hmac_sha256(&self.secret, body)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        HeaderValue::from_str(&signature).expect("the signature consists of hex digits")
+    }
+}
+
+fn hmac_sha256(secret: &[u8], message: &[u8]) -> [u8; 32] {
+    Hmac::<Sha256>::new_from_slice(secret)
+        .expect("hmac accepts keys of any length")
+        .chain_update(message)
+        .finalize()
+        .into_bytes()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(secret: &[u8], message: &[u8]) -> String {
+        HmacSigner {
+            header: HeaderName::from_static("x-signature"),
+            secret: secret.to_vec(),
+        }
+        .sign(message)
+        .to_str()
+        .unwrap()
+        .to_string()
+    }
+
+    // the test cases of RFC 4231 with their HMAC-SHA256 results
+
+    #[test]
+    fn rfc_4231_test_case_1() {
+        assert_eq!(
+            signature(&[0x0b; 20], b"Hi There"),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+    }
+
+    #[test]
+    fn rfc_4231_test_case_2_key_shorter_than_the_output() {
+        assert_eq!(
+            signature(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn rfc_4231_test_case_3() {
+        assert_eq!(
+            signature(&[0xaa; 20], &[0xdd; 50]),
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe"
+        );
+    }
+
+    #[test]
+    fn rfc_4231_test_case_4() {
+        let secret = (0x01..=0x19).collect::<Vec<u8>>();
+
+        assert_eq!(
+            signature(&secret, &[0xcd; 50]),
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b"
+        );
+    }
+
+    #[test]
+    fn rfc_4231_test_case_6_key_longer_than_the_block_size() {
+        assert_eq!(
+            signature(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn rfc_4231_test_case_7_key_and_data_longer_than_the_block_size() {
+        assert_eq!(
+            signature(
+                &[0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm."
+            ),
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use error::InvalidResponseSchema;
+
+pub struct ResponseSchema {
+    validator: jsonschema::Validator,
+}
+
+impl ResponseSchema {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InvalidResponseSchema> {
+        let path = path.as_ref();
+        let failed = |error: String| InvalidResponseSchema {
+            path: path.into(),
+            error,
+        };
+
+        let schema = std::fs::read_to_string(path).map_err(|e| failed(e.to_string()))?;
+        let schema = serde_json::from_str(&schema).map_err(|e| failed(e.to_string()))?;
+        let validator = jsonschema::validator_for(&schema).map_err(|e| failed(e.to_string()))?;
+
+        Ok(Self { validator })
+    }
+
+    // one line per violation, prefixed with the json pointer of the violating value in the data
+    pub fn violations(&self, data: &serde_json::Value) -> Vec<String> {
+        self.validator
+            .iter_errors(data)
+            .map(|error| {
+                let instance_path = error.instance_path().to_string();
+                let instance_path = if instance_path.is_empty() {
+                    "/"
+                } else {
+                    instance_path.as_str()
+                };
+
+                format!("{instance_path}: {error}")
+            })
+            .collect()
+    }
+}
+
+pub mod error {
+    use std::path::PathBuf;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidResponseSchema: path = '{path}', error = {error}")]
+    pub struct InvalidResponseSchema {
+        pub path: PathBuf,
+        pub error: String,
+    }
+}
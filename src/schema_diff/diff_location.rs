@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiffLocationSegmentType {
     DefaultValue,
     Description,
@@ -27,7 +27,7 @@ pub enum DiffLocationSegmentType {
 }
 
 impl DiffLocationSegmentType {
-    fn as_str(&self) -> &str {
+    pub fn as_str(&self) -> &str {
         match self {
             DiffLocationSegmentType::DefaultValue => "DefaultValue",
             DiffLocationSegmentType::Description => "Description",
@@ -57,16 +57,16 @@ impl DiffLocationSegmentType {
     }
 }
 
-#[derive(Clone)]
-struct DiffLocationSegment<'a>(DiffLocationSegmentType, Option<&'a str>);
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DiffLocationSegment(DiffLocationSegmentType, Option<String>);
 
-#[derive(Clone)]
-pub struct DiffLocation<'a> {
-    segments: Vec<DiffLocationSegment<'a>>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLocation {
+    segments: Vec<DiffLocationSegment>,
 }
 
-impl<'a> DiffLocation<'a> {
-    pub fn new<NameType: Into<Option<&'a str>>>(
+impl DiffLocation {
+    pub fn new<'a, NameType: Into<Option<&'a str>>>(
         diff_type: DiffLocationSegmentType,
         name: NameType,
     ) -> Self {
@@ -75,7 +75,7 @@ impl<'a> DiffLocation<'a> {
         }
     }
 
-    pub fn push<NameType: Into<Option<&'a str>>>(
+    pub fn push<'a, NameType: Into<Option<&'a str>>>(
         &self,
         diff_type: DiffLocationSegmentType,
         name: NameType,
@@ -84,27 +84,33 @@ impl<'a> DiffLocation<'a> {
         ret.segments.push(DiffLocationSegment::new(diff_type, name));
         ret
     }
+
+    pub fn root_name(&self) -> Option<&str> {
+        self.segments
+            .first()
+            .and_then(|segment| segment.1.as_deref())
+    }
 }
 
-impl<'a> DiffLocationSegment<'a> {
-    fn new<NameType: Into<Option<&'a str>>>(
+impl DiffLocationSegment {
+    fn new<'a, NameType: Into<Option<&'a str>>>(
         diff_type: DiffLocationSegmentType,
         name: NameType,
     ) -> Self {
-        Self(diff_type, name.into())
+        Self(diff_type, name.into().map(String::from))
     }
 }
 
-impl std::fmt::Display for DiffLocationSegment<'_> {
+impl std::fmt::Display for DiffLocationSegment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.1 {
+        match &self.1 {
             Some(name) => write!(f, "{}({})", self.0.as_str(), name),
             None => write!(f, "{}", self.0.as_str()),
         }
     }
 }
 
-impl std::fmt::Display for DiffLocation<'_> {
+impl std::fmt::Display for DiffLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut buffer = String::new();
         for (count, segment) in self.segments.iter().enumerate() {
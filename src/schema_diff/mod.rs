@@ -1,8 +1,17 @@
 mod diff_location;
 mod named;
+mod rename_detection;
+mod schema_change;
+mod schema_source;
 mod try_into_service_document;
 
-use std::fmt::Display;
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    ffi::OsString,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use async_graphql::{Name, Positioned, Value};
 use async_graphql_parser::types::{
@@ -10,10 +19,19 @@ use async_graphql_parser::types::{
     InputObjectType, InputValueDefinition, InterfaceType, ObjectType, SchemaDefinition,
     ServiceDocument, Type, TypeDefinition, TypeKind, TypeSystemDefinition, UnionType,
 };
-use diff_location::{DiffLocation, DiffLocationSegmentType};
+pub use diff_location::{DiffLocation, DiffLocationSegmentType};
+use error::{CannotComputeSchemaDiff, SchemaPairFailed, UnknownType};
 use named::Named;
-use try_into_service_document::TryIntoServiceDocument;
+use rename_detection::detect_type_renames;
+pub use schema_change::{SchemaChange, SchemaChangeKind};
+pub use schema_source::SchemaSource;
+pub use try_into_service_document::TryIntoServiceDocument;
+
+use crate::operations::OperationKind;
+
+type SchemaChanges = RefCell<Vec<SchemaChange>>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChangeType {
     Breaking,
     NonBreaking,
@@ -30,38 +48,355 @@ impl std::fmt::Display for ChangeType {
     }
 }
 
-pub fn diff_schema(
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffFormat {
+    #[default]
+    Text,
+    // workflow commands that github actions renders as annotations
+    Github,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiffSchemaOptions {
+    pub summary_json: Option<PathBuf>,
+    pub types: Vec<String>,
+    pub root: Option<OperationKind>,
+    pub detect_renames: bool,
+    pub no_summary: bool,
+    pub format: DiffFormat,
+}
+
+// all the changes between the two sdl documents, nothing is printed
+pub fn compute_schema_diff(
+    schema_left: &str,
+    schema_right: &str,
+) -> Result<Vec<SchemaChange>, CannotComputeSchemaDiff> {
+    let schema_left = schema_left.try_into_service_document()?;
+    let schema_right = schema_right.try_into_service_document()?;
+
+    Ok(filtered_changes(
+        &schema_left,
+        &schema_right,
+        &DiffSchemaOptions::default(),
+    )?)
+}
+
+// only the type filters and the rename detection of the options are used, nothing is printed
+fn compute_filtered_schema_diff(
     schema_left: impl TryIntoServiceDocument<Error: std::error::Error>,
     schema_right: impl TryIntoServiceDocument<Error: std::error::Error>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    options: &DiffSchemaOptions,
+) -> Result<Vec<SchemaChange>, Box<dyn std::error::Error>> {
     let schema_left = schema_left.try_into_service_document()?;
     let schema_right = schema_right.try_into_service_document()?;
 
+    Ok(filtered_changes(&schema_left, &schema_right, options)?)
+}
+
+pub fn diff_schema(
+    schema_left: impl TryIntoServiceDocument<Error: std::error::Error>,
+    schema_right: impl TryIntoServiceDocument<Error: std::error::Error>,
+    options: &DiffSchemaOptions,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let changes = compute_filtered_schema_diff(schema_left, schema_right, options)?;
+
+    for change in changes.iter() {
+        println!("{}", render_change(change, options.format, None));
+    }
+
+    if !options.no_summary {
+        println!("{}", summary_line(&changes));
+    }
+
+    if let Some(summary_json_path) = options.summary_json.as_ref() {
+        std::fs::write(
+            summary_json_path,
+            serde_json::to_string_pretty(&summarize_changes(&changes))?,
+        )?;
+    }
+
+    Ok(changes
+        .iter()
+        .any(|change| change.change_type == ChangeType::Breaking))
+}
+
+pub fn diff_schema_dirs(
+    left_dir: impl AsRef<Path>,
+    right_dir: impl AsRef<Path>,
+    options: &DiffSchemaOptions,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let left_dir = left_dir.as_ref();
+    let right_dir = right_dir.as_ref();
+
+    let left_files = schema_file_names(left_dir)?;
+    let right_files = schema_file_names(right_dir)?;
+
+    let pair_results = std::thread::scope(|scope| {
+        let handles = left_files
+            .intersection(&right_files)
+            .map(|file_name| {
+                let left_path = left_dir.join(file_name);
+                let right_path = right_dir.join(file_name);
+
+                let handle = scope.spawn(move || -> Result<Vec<SchemaChange>, String> {
+                    let schema_left = left_path
+                        .try_into_service_document()
+                        .map_err(|e| e.to_string())?;
+                    let schema_right = right_path
+                        .try_into_service_document()
+                        .map_err(|e| e.to_string())?;
+
+                    filtered_changes(&schema_left, &schema_right, options)
+                        .map_err(|e| e.to_string())
+                });
+
+                (file_name.to_string_lossy().to_string(), handle)
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|(name, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("the diff thread panicked".to_string()));
+                (name, result)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut pairs = serde_json::Map::new();
+    let mut all_changes = Vec::new();
+
+    for (name, result) in pair_results {
+        let changes = result.map_err(|message| SchemaPairFailed {
+            name: name.clone(),
+            message,
+        })?;
+
+        println!("== {name} ==");
+        for change in changes.iter() {
+            println!("{}", render_change(change, options.format, Some(&name)));
+        }
+
+        pairs.insert(name, summarize_changes(&changes));
+        all_changes.extend(changes);
+    }
+
+    let unpaired = left_files
+        .symmetric_difference(&right_files)
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    for name in unpaired.iter() {
+        println!("== {name} == only present in one of the directories");
+    }
+
+    if !options.no_summary {
+        println!("{}", summary_line(&all_changes));
+    }
+
+    if let Some(summary_json_path) = options.summary_json.as_ref() {
+        let mut summary = summarize_changes(&all_changes);
+        summary["pairs"] = serde_json::Value::Object(pairs);
+        summary["unpaired"] = serde_json::json!(unpaired);
+
+        std::fs::write(summary_json_path, serde_json::to_string_pretty(&summary)?)?;
+    }
+
+    Ok(all_changes
+        .iter()
+        .any(|change| change.change_type == ChangeType::Breaking))
+}
+
+fn schema_file_names(dir: &Path) -> Result<BTreeSet<OsString>, std::io::Error> {
+    let mut file_names = BTreeSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            file_names.insert(entry.file_name());
+        }
+    }
+
+    Ok(file_names)
+}
+
+fn filtered_changes(
+    schema_left: &ServiceDocument,
+    schema_right: &ServiceDocument,
+    options: &DiffSchemaOptions,
+) -> Result<Vec<SchemaChange>, UnknownType> {
+    let mut type_filter = options.types.clone();
+    if let Some(root) = options.root {
+        // the root type can have a different name on the two sides
+        for service_document in [schema_left, schema_right] {
+            let root_type_name = root_type_name(service_document, root);
+            if !type_filter.contains(&root_type_name) {
+                type_filter.push(root_type_name);
+            }
+        }
+    }
+
+    for type_name in type_filter.iter() {
+        if !has_type_definition(schema_left, type_name)
+            && !has_type_definition(schema_right, type_name)
+        {
+            return Err(UnknownType {
+                name: type_name.clone(),
+            });
+        }
+    }
+
+    let mut changes = collect_changes(schema_left, schema_right);
+    if options.detect_renames {
+        changes = detect_type_renames(schema_left, schema_right, changes);
+    }
+
+    Ok(changes
+        .into_iter()
+        .filter(|change| {
+            type_filter.is_empty()
+                || type_filter
+                    .iter()
+                    .any(|type_name| change.is_under_type(type_name))
+        })
+        .collect())
+}
+
+fn root_type_name(service_document: &ServiceDocument, root: OperationKind) -> String {
+    filter_schemas_of_service_document(service_document)
+        .find_map(|schema| match root {
+            OperationKind::Query => schema.query.as_ref(),
+            OperationKind::Mutation => schema.mutation.as_ref(),
+            OperationKind::Subscription => schema.subscription.as_ref(),
+        })
+        .map_or_else(
+            || default_root_type_name(root).to_string(),
+            |name| name.node.to_string(),
+        )
+}
+
+fn default_root_type_name(root: OperationKind) -> &'static str {
+    match root {
+        OperationKind::Query => "Query",
+        OperationKind::Mutation => "Mutation",
+        OperationKind::Subscription => "Subscription",
+    }
+}
+
+fn has_type_definition(service_document: &ServiceDocument, type_name: &str) -> bool {
+    filter_types_of_service_document(service_document)
+        .any(|type_definition| type_definition.name() == type_name)
+}
+
+fn render_change(change: &SchemaChange, format: DiffFormat, pair_name: Option<&str>) -> String {
+    match format {
+        DiffFormat::Text => change.to_string(),
+        DiffFormat::Github => {
+            let (level, title) = match change.change_type {
+                ChangeType::Breaking => ("error", "Breaking schema change"),
+                ChangeType::NonBreaking | ChangeType::Unknown => ("warning", "Schema change"),
+            };
+            let title = match pair_name {
+                Some(pair_name) => format!("{title} in {pair_name}"),
+                None => title.to_string(),
+            };
+
+            format!(
+                "::{level} title={}::{}",
+                escape_workflow_command_property(&title),
+                escape_workflow_command_data(&change.to_string()),
+            )
+        }
+    }
+}
+
+fn escape_workflow_command_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_workflow_command_property(property: &str) -> String {
+    escape_workflow_command_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+fn summary_line(changes: &[SchemaChange]) -> String {
+    if changes.is_empty() {
+        return "No changes.".to_string();
+    }
+
+    let count = |change_type: ChangeType| {
+        changes
+            .iter()
+            .filter(|change| change.change_type == change_type)
+            .count()
+    };
+
+    format!(
+        "{} {}, {} {}, {} {} changes",
+        count(ChangeType::Breaking),
+        ChangeType::Breaking,
+        count(ChangeType::NonBreaking),
+        ChangeType::NonBreaking,
+        count(ChangeType::Unknown),
+        ChangeType::Unknown,
+    )
+}
+
+fn summarize_changes(changes: &[SchemaChange]) -> serde_json::Value {
+    let count = |change_type: ChangeType| {
+        changes
+            .iter()
+            .filter(|change| change.change_type == change_type)
+            .count()
+    };
+
+    serde_json::json!({
+        "counts": {
+            ChangeType::Breaking.to_string(): count(ChangeType::Breaking),
+            ChangeType::NonBreaking.to_string(): count(ChangeType::NonBreaking),
+            ChangeType::Unknown.to_string(): count(ChangeType::Unknown),
+        },
+        "breaking": count(ChangeType::Breaking) > 0,
+    })
+}
+
+fn collect_changes(
+    schema_left: &ServiceDocument,
+    schema_right: &ServiceDocument,
+) -> Vec<SchemaChange> {
+    let changes = SchemaChanges::default();
+
     compare_iterators(
+        &changes,
         DiffLocation::new(DiffLocationSegmentType::DirectiveDefinition, None),
-        || filter_directive_definitions_of_service_document(&schema_left),
-        || filter_directive_definitions_of_service_document(&schema_right),
+        || filter_directive_definitions_of_service_document(schema_left),
+        || filter_directive_definitions_of_service_document(schema_right),
         |_new_item| ChangeType::NonBreaking,
-        compare_directive_definitions,
+        |left, right| compare_directive_definitions(&changes, left, right),
     );
 
     compare_iterators(
+        &changes,
         DiffLocation::new(DiffLocationSegmentType::SchemaDefinition, None),
-        || filter_schemas_of_service_document(&schema_left),
-        || filter_schemas_of_service_document(&schema_right),
+        || filter_schemas_of_service_document(schema_left),
+        || filter_schemas_of_service_document(schema_right),
         |_new_item| ChangeType::Breaking,
-        compare_schema_definitions,
+        |left, right| compare_schema_definitions(&changes, left, right),
     );
 
     compare_iterators(
+        &changes,
         DiffLocation::new(DiffLocationSegmentType::TypeDefinition, None),
-        || filter_types_of_service_document(&schema_left),
-        || filter_types_of_service_document(&schema_right),
+        || filter_types_of_service_document(schema_left),
+        || filter_types_of_service_document(schema_right),
         |_new_item| ChangeType::NonBreaking,
-        compare_type_definitions,
+        |left, right| compare_type_definitions(&changes, left, right),
     );
 
-    Ok(())
+    changes.into_inner()
 }
 
 fn filter_directive_definitions_of_service_document(
@@ -110,48 +445,58 @@ fn compare_iterators<
     LeftIteratorType: Iterator<Item = &'a T>,
     RightIteratorType: Iterator<Item = &'b T>,
 >(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     left_iter_generator: impl Fn() -> LeftIteratorType,
     right_iter_generator: impl Fn() -> RightIteratorType,
     change_type_if_added: impl Fn(&T) -> ChangeType,
     item_comparator_fn: impl Fn(&T, &T),
 ) {
+    // the items are visited in name order, so the declaration order of the sdl does not affect the output
+    let mut rights = right_iter_generator().collect::<Vec<_>>();
+    rights.sort_by_key(|right| right.name());
+    let mut lefts = left_iter_generator().collect::<Vec<_>>();
+    lefts.sort_by_key(|left| left.name());
+
     // item is added
     // non-breaking changes
-    for right in right_iter_generator() {
+    for right in rights {
         match left_iter_generator().find(|left| left.name() == right.name()) {
             Some(left) => item_comparator_fn(left, right),
             None => {
-                println!(
-                    "{}: item is added to the right, name = '{}' (breaking = {})",
-                    diff_location,
-                    right.name(),
-                    change_type_if_added(right),
-                );
+                changes.borrow_mut().push(SchemaChange {
+                    location: diff_location.clone(),
+                    kind: SchemaChangeKind::ItemAdded {
+                        name: right.name().to_string(),
+                    },
+                    change_type: change_type_if_added(right),
+                });
             }
         }
     }
 
     // item is removed
     // breaking changes
-    for left in left_iter_generator() {
+    for left in lefts {
         match right_iter_generator().find(|right| right.name() == left.name()) {
             Some(_right) => {
                 // already compared
             }
             None => {
-                println!(
-                    "{}: item is removed, name = '{}' (breaking = {})",
-                    diff_location,
-                    left.name(),
-                    ChangeType::Breaking,
-                );
+                changes.borrow_mut().push(SchemaChange {
+                    location: diff_location.clone(),
+                    kind: SchemaChangeKind::ItemRemoved {
+                        name: left.name().to_string(),
+                    },
+                    change_type: ChangeType::Breaking,
+                });
             }
         }
     }
 }
 
 fn compare_directive_definitions(
+    changes: &SchemaChanges,
     definition_left: &DirectiveDefinition,
     definition_right: &DirectiveDefinition,
 ) {
@@ -179,6 +524,7 @@ fn compare_directive_definitions(
     );
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Description, None),
         ChangeType::NonBreaking,
         ChangeType::NonBreaking,
@@ -193,6 +539,7 @@ fn compare_directive_definitions(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::InputArgument, None),
         || arguments_left.iter().map(|positioned| &positioned.node),
         || arguments_right.iter().map(|positioned| &positioned.node),
@@ -205,6 +552,7 @@ fn compare_directive_definitions(
         },
         |left, right| {
             compare_input_value_definitions(
+                changes,
                 diff_location.push(DiffLocationSegmentType::InputArgument, Some(right.name())),
                 left,
                 right,
@@ -213,6 +561,7 @@ fn compare_directive_definitions(
     );
 
     compare_comparables(
+        changes,
         diff_location.push(DiffLocationSegmentType::IsRepeatable, None),
         is_repeatable_left,
         is_repeatable_right,
@@ -225,6 +574,7 @@ fn compare_directive_definitions(
 }
 
 fn compare_schema_definitions(
+    changes: &SchemaChanges,
     definition_left: &SchemaDefinition,
     definition_right: &SchemaDefinition,
 ) {
@@ -250,6 +600,7 @@ fn compare_schema_definitions(
     );
 
     compare_comparables(
+        changes,
         diff_location.push(DiffLocationSegmentType::Extends, None),
         extend_left,
         extend_right,
@@ -257,12 +608,14 @@ fn compare_schema_definitions(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Directive, None),
         || directives_left.iter().map(|positioned| &positioned.node),
         || directives_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::Unknown,
         |left, right| {
             compare_const_directives(
+                changes,
                 diff_location
                     .push(DiffLocationSegmentType::Directive, Some(right.name()))
                     .clone(),
@@ -273,6 +626,7 @@ fn compare_schema_definitions(
     );
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Query, None),
         ChangeType::Breaking,
         ChangeType::NonBreaking,
@@ -287,6 +641,7 @@ fn compare_schema_definitions(
     );
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Mutation, None),
         ChangeType::Breaking,
         ChangeType::NonBreaking,
@@ -301,6 +656,7 @@ fn compare_schema_definitions(
     );
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Subscription, None),
         ChangeType::Breaking,
         ChangeType::NonBreaking,
@@ -315,7 +671,11 @@ fn compare_schema_definitions(
     );
 }
 
-fn compare_type_definitions(definition_left: &TypeDefinition, definition_right: &TypeDefinition) {
+fn compare_type_definitions(
+    changes: &SchemaChanges,
+    definition_left: &TypeDefinition,
+    definition_right: &TypeDefinition,
+) {
     let TypeDefinition {
         extend: extend_left,
         description: description_left,
@@ -346,24 +706,24 @@ fn compare_type_definitions(definition_left: &TypeDefinition, definition_right:
         (TypeKind::Object(type_left), TypeKind::Object(type_right)) => {
             diff_location =
                 DiffLocation::new(DiffLocationSegmentType::ObjectDefinition, Some(name));
-            compare_object_types(diff_location.clone(), type_left, type_right)
+            compare_object_types(changes, diff_location.clone(), type_left, type_right)
         }
         (TypeKind::Interface(type_left), TypeKind::Interface(type_right)) => {
             diff_location =
                 DiffLocation::new(DiffLocationSegmentType::InterfaceDefinition, Some(name));
-            compare_interface_types(diff_location.clone(), type_left, type_right)
+            compare_interface_types(changes, diff_location.clone(), type_left, type_right)
         }
         (TypeKind::Union(type_left), TypeKind::Union(type_right)) => {
             diff_location = DiffLocation::new(DiffLocationSegmentType::UnionDefinition, Some(name));
-            compare_union_types(diff_location.clone(), type_left, type_right)
+            compare_union_types(changes, diff_location.clone(), type_left, type_right)
         }
         (TypeKind::Enum(type_left), TypeKind::Enum(type_right)) => {
             diff_location = DiffLocation::new(DiffLocationSegmentType::EnumDefinition, Some(name));
-            compare_enum_types(diff_location.clone(), type_left, type_right)
+            compare_enum_types(changes, diff_location.clone(), type_left, type_right)
         }
         (TypeKind::InputObject(type_left), TypeKind::InputObject(type_right)) => {
             diff_location = DiffLocation::new(DiffLocationSegmentType::InputObject, Some(name));
-            compare_input_object_types(diff_location.clone(), type_left, type_right)
+            compare_input_object_types(changes, diff_location.clone(), type_left, type_right)
         }
         // comparing types of different kinds, the code will fail to build this way if a new type is introduced
         (TypeKind::Scalar, _type_kind_right)
@@ -372,16 +732,18 @@ fn compare_type_definitions(definition_left: &TypeDefinition, definition_right:
         | (TypeKind::Union(_), _type_kind_right)
         | (TypeKind::Enum(_), _type_kind_right)
         | (TypeKind::InputObject(_), _type_kind_right) => {
-            println!(
-                "{}: type mismatch, name = '{}' (breaking = {})",
-                diff_location,
-                name,
-                ChangeType::Breaking,
-            );
+            changes.borrow_mut().push(SchemaChange {
+                location: diff_location.clone(),
+                kind: SchemaChangeKind::TypeMismatch {
+                    name: name.to_string(),
+                },
+                change_type: ChangeType::Breaking,
+            });
         }
     }
 
     compare_comparables(
+        changes,
         diff_location.push(DiffLocationSegmentType::Extends, None),
         extend_left,
         extend_right,
@@ -389,6 +751,7 @@ fn compare_type_definitions(definition_left: &TypeDefinition, definition_right:
     );
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Description, None),
         ChangeType::NonBreaking,
         ChangeType::NonBreaking,
@@ -403,12 +766,14 @@ fn compare_type_definitions(definition_left: &TypeDefinition, definition_right:
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Directive, None),
         || directives_left.iter().map(|positioned| &positioned.node),
         || directives_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::Unknown,
         |left, right| {
             compare_const_directives(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Directive, Some(right.name())),
                 left,
                 right,
@@ -417,7 +782,9 @@ fn compare_type_definitions(definition_left: &TypeDefinition, definition_right:
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn compare_optional_strings(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     change_type_if_different: ChangeType,
     change_type_if_added: ChangeType,
@@ -434,10 +801,14 @@ fn compare_optional_strings(
                     right = "?";
                 }
 
-                println!(
-                    "{}: left value = '{}', right value = '{}' (breaking = {})",
-                    diff_location, left, right, change_type_if_different,
-                );
+                changes.borrow_mut().push(SchemaChange {
+                    location: diff_location,
+                    kind: SchemaChangeKind::ValueChanged {
+                        left: left.to_string(),
+                        right: right.to_string(),
+                    },
+                    change_type: change_type_if_different,
+                });
             }
         }
         (None, None) => (),
@@ -447,10 +818,13 @@ fn compare_optional_strings(
                 left = "?";
             }
 
-            println!(
-                "{}: item is removed, value = '{}' (breaking = {})",
-                diff_location, left, change_type_if_removed,
-            );
+            changes.borrow_mut().push(SchemaChange {
+                location: diff_location,
+                kind: SchemaChangeKind::ValueRemoved {
+                    value: left.to_string(),
+                },
+                change_type: change_type_if_removed,
+            });
         }
         // non-breaking change
         (None, Some(mut right)) => {
@@ -458,15 +832,19 @@ fn compare_optional_strings(
                 right = "?";
             }
 
-            println!(
-                "{}: added to right, value = '{}' (breaking = {})",
-                diff_location, right, change_type_if_added,
-            );
+            changes.borrow_mut().push(SchemaChange {
+                location: diff_location,
+                kind: SchemaChangeKind::ValueAdded {
+                    value: right.to_string(),
+                },
+                change_type: change_type_if_added,
+            });
         }
     }
 }
 
 fn compare_object_types(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     type_left: &ObjectType,
     type_right: &ObjectType,
@@ -482,6 +860,7 @@ fn compare_object_types(
     } = type_right;
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Implements, None),
         || implements_left.iter().map(|positioned| &positioned.node),
         || implements_right.iter().map(|positioned| &positioned.node),
@@ -492,12 +871,14 @@ fn compare_object_types(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Field, None),
         || fields_left.iter().map(|positioned| &positioned.node),
         || fields_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::NonBreaking,
         |left, right| {
             compare_field_definitions(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Field, Some(left.name())),
                 left,
                 right,
@@ -507,6 +888,7 @@ fn compare_object_types(
 }
 
 fn compare_interface_types(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     type_left: &InterfaceType,
     type_right: &InterfaceType,
@@ -522,6 +904,7 @@ fn compare_interface_types(
     } = type_right;
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Implements, None),
         || implements_left.iter().map(|positioned| &positioned.node),
         || implements_right.iter().map(|positioned| &positioned.node),
@@ -532,12 +915,14 @@ fn compare_interface_types(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Field, None),
         || fields_left.iter().map(|positioned| &positioned.node),
         || fields_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::NonBreaking,
         |left, right| {
             compare_field_definitions(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Field, Some(right.name())),
                 left,
                 right,
@@ -546,7 +931,12 @@ fn compare_interface_types(
     );
 }
 
-fn compare_union_types(diff_location: DiffLocation, type_left: &UnionType, type_right: &UnionType) {
+fn compare_union_types(
+    changes: &SchemaChanges,
+    diff_location: DiffLocation,
+    type_left: &UnionType,
+    type_right: &UnionType,
+) {
     let UnionType {
         members: members_left,
     } = type_left;
@@ -556,6 +946,7 @@ fn compare_union_types(diff_location: DiffLocation, type_left: &UnionType, type_
     } = type_right;
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::UnionMemberDefinition, None),
         || members_left.iter().map(|positioned| &positioned.node),
         || members_right.iter().map(|positioned| &positioned.node),
@@ -566,7 +957,12 @@ fn compare_union_types(diff_location: DiffLocation, type_left: &UnionType, type_
     );
 }
 
-fn compare_enum_types(diff_location: DiffLocation, type_left: &EnumType, type_right: &EnumType) {
+fn compare_enum_types(
+    changes: &SchemaChanges,
+    diff_location: DiffLocation,
+    type_left: &EnumType,
+    type_right: &EnumType,
+) {
     let EnumType {
         values: values_left,
     } = type_left;
@@ -576,12 +972,14 @@ fn compare_enum_types(diff_location: DiffLocation, type_left: &EnumType, type_ri
     } = type_right;
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::EnumValueDefinition, None),
         || values_left.iter().map(|positioned| &positioned.node),
         || values_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::NonBreaking,
         |left, right| {
             compare_enum_value_definitions(
+                changes,
                 diff_location.push(
                     DiffLocationSegmentType::EnumValueDefinition,
                     Some(right.name()),
@@ -594,6 +992,7 @@ fn compare_enum_types(diff_location: DiffLocation, type_left: &EnumType, type_ri
 }
 
 fn compare_input_object_types(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     type_left: &InputObjectType,
     type_right: &InputObjectType,
@@ -607,6 +1006,7 @@ fn compare_input_object_types(
     } = type_right;
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Field, None),
         || fields_left.iter().map(|positioned| &positioned.node),
         || fields_right.iter().map(|positioned| &positioned.node),
@@ -619,6 +1019,7 @@ fn compare_input_object_types(
         },
         |left, right| {
             compare_input_value_definitions(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Field, Some(right.name())),
                 left,
                 right,
@@ -628,6 +1029,7 @@ fn compare_input_object_types(
 }
 
 fn compare_field_definitions(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     definition_left: &FieldDefinition,
     definition_right: &FieldDefinition,
@@ -651,6 +1053,7 @@ fn compare_field_definitions(
     assert_eq!(name_left.node.as_str(), name_right.node.as_str());
 
     compare_types(
+        changes,
         diff_location.push(DiffLocationSegmentType::Type, None),
         &ty_left.node,
         &ty_right.node,
@@ -658,6 +1061,7 @@ fn compare_field_definitions(
     );
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Description, None),
         ChangeType::NonBreaking,
         ChangeType::NonBreaking,
@@ -672,6 +1076,7 @@ fn compare_field_definitions(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::InputArgument, None),
         || arguments_left.iter().map(|positioned| &positioned.node),
         || arguments_right.iter().map(|positioned| &positioned.node),
@@ -684,6 +1089,7 @@ fn compare_field_definitions(
         },
         |left, right| {
             compare_input_value_definitions(
+                changes,
                 diff_location.push(DiffLocationSegmentType::InputArgument, Some(right.name())),
                 left,
                 right,
@@ -692,12 +1098,14 @@ fn compare_field_definitions(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Directive, None),
         || directives_left.iter().map(|positioned| &positioned.node),
         || directives_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::Unknown,
         |left, right| {
             compare_const_directives(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Directive, Some(right.name())),
                 left,
                 right,
@@ -707,6 +1115,7 @@ fn compare_field_definitions(
 }
 
 fn compare_const_directives(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     directive_left: &ConstDirective,
     directive_right: &ConstDirective,
@@ -724,6 +1133,7 @@ fn compare_const_directives(
     assert_eq!(name_left.node.as_str(), name_right.node.as_str());
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::DirectiveArgument, None),
         || {
             arguments_left.iter()
@@ -736,6 +1146,7 @@ fn compare_const_directives(
         |_new_item| ChangeType::Unknown,
         |left, right| {
             compare_const_directive_argument_value(
+                changes,
                 diff_location.push(
                     DiffLocationSegmentType::DirectiveArgument,
                     Some(right.name()),
@@ -748,6 +1159,7 @@ fn compare_const_directives(
 }
 
 fn compare_enum_value_definitions(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     definition_left: &EnumValueDefinition,
     definition_right: &EnumValueDefinition,
@@ -767,6 +1179,7 @@ fn compare_enum_value_definitions(
     assert_eq!(value_left.node.as_str(), value_right.node.as_str());
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Description, None),
         ChangeType::NonBreaking,
         ChangeType::NonBreaking,
@@ -781,12 +1194,14 @@ fn compare_enum_value_definitions(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Directive, None),
         || directives_left.iter().map(|positioned| &positioned.node),
         || directives_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::Unknown,
         |left, right| {
             compare_const_directives(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Directive, Some(right.name())),
                 left,
                 right,
@@ -796,6 +1211,7 @@ fn compare_enum_value_definitions(
 }
 
 fn compare_input_value_definitions(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     definition_left: &InputValueDefinition,
     definition_right: &InputValueDefinition,
@@ -819,6 +1235,7 @@ fn compare_input_value_definitions(
     assert_eq!(name_left.node.as_str(), name_right.node.as_str());
 
     compare_types(
+        changes,
         diff_location.push(DiffLocationSegmentType::Type, None),
         &ty_left.node,
         &ty_right.node,
@@ -834,38 +1251,42 @@ fn compare_input_value_definitions(
         ) {
             (Some(left), Some(right)) => {
                 if left != right {
-                    println!(
-                        "{}: left value = '{}', right value = '{}' (breaking = {})",
-                        diff_location,
-                        left,
-                        right,
-                        ChangeType::Breaking,
-                    );
+                    changes.borrow_mut().push(SchemaChange {
+                        location: diff_location,
+                        kind: SchemaChangeKind::ValueChanged {
+                            left: left.to_string(),
+                            right: right.to_string(),
+                        },
+                        change_type: ChangeType::Breaking,
+                    });
                 }
             }
             (None, None) => (),
             // breaking change
             (Some(left), None) => {
-                println!(
-                    "{}: item is removed, value = '{}' (breaking = {})",
-                    diff_location,
-                    left,
-                    ChangeType::Breaking,
-                );
+                changes.borrow_mut().push(SchemaChange {
+                    location: diff_location,
+                    kind: SchemaChangeKind::ValueRemoved {
+                        value: left.to_string(),
+                    },
+                    change_type: ChangeType::Breaking,
+                });
             }
             // non-breaking change
             (None, Some(right)) => {
-                println!(
-                    "{}: added to right, value = '{}' (breaking = {})",
-                    diff_location,
-                    right,
-                    ChangeType::NonBreaking,
-                );
+                changes.borrow_mut().push(SchemaChange {
+                    location: diff_location,
+                    kind: SchemaChangeKind::ValueAdded {
+                        value: right.to_string(),
+                    },
+                    change_type: ChangeType::NonBreaking,
+                });
             }
         }
     }
 
     compare_optional_strings(
+        changes,
         diff_location.push(DiffLocationSegmentType::Description, None),
         ChangeType::NonBreaking,
         ChangeType::NonBreaking,
@@ -880,12 +1301,14 @@ fn compare_input_value_definitions(
     );
 
     compare_iterators(
+        changes,
         diff_location.push(DiffLocationSegmentType::Directive, None),
         || directives_left.iter().map(|positioned| &positioned.node),
         || directives_right.iter().map(|positioned| &positioned.node),
         |_new_item| ChangeType::Unknown,
         |left, right| {
             compare_const_directives(
+                changes,
                 diff_location.push(DiffLocationSegmentType::Directive, Some(right.name())),
                 left,
                 right,
@@ -895,11 +1318,13 @@ fn compare_input_value_definitions(
 }
 
 fn compare_const_directive_argument_value(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     arg_left: &(Positioned<Name>, Positioned<Value>),
     arg_right: &(Positioned<Name>, Positioned<Value>),
 ) {
     compare_comparables(
+        changes,
         diff_location.push(DiffLocationSegmentType::DirectiveArgument, None),
         &arg_left.1.node,
         &arg_right.1.node,
@@ -908,16 +1333,21 @@ fn compare_const_directive_argument_value(
 }
 
 fn compare_comparables<T: Display + Eq + PartialEq + ?Sized>(
+    changes: &SchemaChanges,
     diff_location: DiffLocation,
     left: &T,
     right: &T,
     change_type: ChangeType,
 ) {
     if *left != *right {
-        println!(
-            "{}: left value = {}, right value = {} (breaking = {})",
-            diff_location, left, right, change_type,
-        )
+        changes.borrow_mut().push(SchemaChange {
+            location: diff_location,
+            kind: SchemaChangeKind::ValueChanged {
+                left: left.to_string(),
+                right: right.to_string(),
+            },
+            change_type,
+        })
     }
 }
 
@@ -966,11 +1396,225 @@ fn compare_types_recursive(left: &Type, right: &Type, is_argument: bool) -> Opti
     }
 }
 
-fn compare_types(diff_location: DiffLocation, left: &Type, right: &Type, is_argument: bool) {
+fn compare_types(
+    changes: &SchemaChanges,
+    diff_location: DiffLocation,
+    left: &Type,
+    right: &Type,
+    is_argument: bool,
+) {
     if let Some(change_type) = compare_types_recursive(left, right, is_argument) {
-        println!(
-            "{}: left value = {}, right value = {} (breaking = {})",
-            diff_location, left, right, change_type,
-        )
+        changes.borrow_mut().push(SchemaChange {
+            location: diff_location,
+            kind: SchemaChangeKind::ValueChanged {
+                left: left.to_string(),
+                right: right.to_string(),
+            },
+            change_type,
+        })
+    }
+}
+
+pub mod error {
+    pub use crate::schema_diff::try_into_service_document::error::CannotLoadServiceDocumentFromString;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("UnknownType: the type does not exist in either schema, name = '{name}'")]
+    pub struct UnknownType {
+        pub name: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum CannotComputeSchemaDiff {
+        #[error(transparent)]
+        InvalidSchema(#[from] CannotLoadServiceDocumentFromString),
+        #[error(transparent)]
+        UnknownType(#[from] UnknownType),
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("SchemaPairFailed: name = '{name}', message = '{message}'")]
+    pub struct SchemaPairFailed {
+        pub name: String,
+        pub message: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reordered_schemas_have_no_changes() {
+        let left = r#"
+            type Query {
+                user(id: ID!, includeDeleted: Boolean): User
+                users(first: Int, after: String): [User!]!
+            }
+
+            type User {
+                id: ID!
+                name: String
+                role: Role!
+            }
+
+            enum Role {
+                ADMIN
+                EDITOR
+                VIEWER
+            }
+
+            input UserFilter {
+                name: String
+                role: Role
+            }
+        "#;
+        let right = r#"
+            input UserFilter {
+                role: Role
+                name: String
+            }
+
+            enum Role {
+                VIEWER
+                ADMIN
+                EDITOR
+            }
+
+            type User {
+                role: Role!
+                name: String
+                id: ID!
+            }
+
+            type Query {
+                users(after: String, first: Int): [User!]!
+                user(includeDeleted: Boolean, id: ID!): User
+            }
+        "#;
+
+        let changes = compute_schema_diff(left, right).unwrap();
+
+        assert!(changes.is_empty(), "{changes:?}");
+    }
+
+    #[test]
+    fn reordered_schemas_still_report_real_changes() {
+        let left = "enum Role { ADMIN EDITOR VIEWER } type Query { role: Role }";
+        let right = "type Query { role: Role } enum Role { VIEWER ADMIN }";
+
+        let changes = compute_schema_diff(left, right).unwrap();
+
+        assert_eq!(changes.len(), 1, "{changes:?}");
+        assert_eq!(changes[0].change_type, ChangeType::Breaking);
+    }
+
+    #[test]
+    fn invalid_schema_is_reported() {
+        let result = compute_schema_diff("type Query { user: User }", "type Query {");
+
+        assert!(matches!(
+            result,
+            Err(CannotComputeSchemaDiff::InvalidSchema(_))
+        ));
+    }
+
+    const RENAMED_ROOTS_LEFT: &str = include_str!("fixtures/renamed_roots_left.graphql");
+    const RENAMED_ROOTS_RIGHT: &str = include_str!("fixtures/renamed_roots_right.graphql");
+
+    #[test]
+    fn schemas_with_renamed_roots_are_diffed() {
+        let changes = compute_schema_diff(RENAMED_ROOTS_LEFT, RENAMED_ROOTS_RIGHT).unwrap();
+
+        assert_eq!(changes.len(), 3, "{changes:?}");
+    }
+
+    #[test]
+    fn root_filter_resolves_renamed_query_root() {
+        let options = DiffSchemaOptions {
+            root: Some(OperationKind::Query),
+            ..Default::default()
+        };
+
+        let changes =
+            compute_filtered_schema_diff(RENAMED_ROOTS_LEFT, RENAMED_ROOTS_RIGHT, &options)
+                .unwrap();
+
+        assert_eq!(changes.len(), 1, "{changes:?}");
+        assert!(changes[0].is_under_type("MyRoot"));
+        assert!(matches!(
+            &changes[0].kind,
+            SchemaChangeKind::ItemRemoved { name } if name == "users"
+        ));
+    }
+
+    #[test]
+    fn root_filter_resolves_renamed_mutation_root() {
+        let options = DiffSchemaOptions {
+            root: Some(OperationKind::Mutation),
+            ..Default::default()
+        };
+
+        let changes =
+            compute_filtered_schema_diff(RENAMED_ROOTS_LEFT, RENAMED_ROOTS_RIGHT, &options)
+                .unwrap();
+
+        assert_eq!(changes.len(), 1, "{changes:?}");
+        assert!(changes[0].is_under_type("MyMutations"));
+    }
+
+    fn diff_with_renames(left: &str, right: &str) -> Vec<SchemaChange> {
+        let options = DiffSchemaOptions {
+            detect_renames: true,
+            ..Default::default()
+        };
+
+        compute_filtered_schema_diff(left, right, &options).unwrap()
+    }
+
+    #[test]
+    fn type_with_the_same_fields_is_renamed() {
+        let changes = diff_with_renames(
+            "type User { id: ID! name: String email: String }",
+            "type Person { id: ID! name: String email: String }",
+        );
+
+        assert_eq!(changes.len(), 1, "{changes:?}");
+        assert!(matches!(
+            &changes[0].kind,
+            SchemaChangeKind::TypeRenamed { left, right } if left == "User" && right == "Person"
+        ));
+    }
+
+    #[test]
+    fn type_below_the_similarity_threshold_is_removed_and_added() {
+        // 6 shared fields of 8, just below the threshold
+        let changes = diff_with_renames(
+            "type User { a: ID! b: String c: String d: Int e: Int f: Boolean g: Boolean }",
+            "type Person { a: ID! b: String c: String d: Int e: Int f: Boolean h: Boolean }",
+        );
+
+        assert_eq!(changes.len(), 2, "{changes:?}");
+        assert!(changes.iter().any(|change| matches!(
+            &change.kind,
+            SchemaChangeKind::ItemRemoved { name } if name == "User"
+        )));
+        assert!(changes.iter().any(|change| matches!(
+            &change.kind,
+            SchemaChangeKind::ItemAdded { name } if name == "Person"
+        )));
+    }
+
+    #[test]
+    fn type_with_two_equal_candidates_is_not_renamed() {
+        let changes = diff_with_renames(
+            "type User { id: ID! name: String }",
+            "type Person { id: ID! name: String } type Member { id: ID! name: String }",
+        );
+
+        assert_eq!(changes.len(), 3, "{changes:?}");
+        assert!(!changes
+            .iter()
+            .any(|change| matches!(change.kind, SchemaChangeKind::TypeRenamed { .. })));
     }
 }
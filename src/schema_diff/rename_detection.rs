@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use async_graphql_parser::types::{ServiceDocument, TypeDefinition, TypeKind};
+
+use super::{
+    filter_types_of_service_document, ChangeType, DiffLocation, DiffLocationSegmentType,
+    SchemaChange, SchemaChangeKind,
+};
+
+// ratio of the shared members (fields with their types, enum values, union members) to all members
+const RENAME_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+pub(super) fn detect_type_renames(
+    schema_left: &ServiceDocument,
+    schema_right: &ServiceDocument,
+    changes: Vec<SchemaChange>,
+) -> Vec<SchemaChange> {
+    let type_location = DiffLocation::new(DiffLocationSegmentType::TypeDefinition, None);
+
+    let removed_types = changes
+        .iter()
+        .filter(|change| change.location == type_location)
+        .filter_map(|change| match &change.kind {
+            SchemaChangeKind::ItemRemoved { name } => find_type(schema_left, name),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let added_types = changes
+        .iter()
+        .filter(|change| change.location == type_location)
+        .filter_map(|change| match &change.kind {
+            SchemaChangeKind::ItemAdded { name } => find_type(schema_right, name),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut candidates = Vec::new();
+    for removed_type in removed_types.iter() {
+        for added_type in added_types.iter() {
+            if let Some(similarity) = type_similarity(removed_type, added_type) {
+                if similarity >= RENAME_SIMILARITY_THRESHOLD {
+                    candidates.push((similarity, removed_type, added_type));
+                }
+            }
+        }
+    }
+    candidates.sort_by(|(left, ..), (right, ..)| right.total_cmp(left));
+
+    let mut renames = Vec::new();
+    let mut renamed_left = BTreeSet::new();
+    let mut renamed_right = BTreeSet::new();
+    let mut ambiguous_left = BTreeSet::new();
+    let mut ambiguous_right = BTreeSet::new();
+    for (similarity, removed_type, added_type) in candidates.iter() {
+        let left = removed_type.name.node.as_str();
+        let right = added_type.name.node.as_str();
+
+        if renamed_left.contains(left)
+            || renamed_right.contains(right)
+            || ambiguous_left.contains(left)
+            || ambiguous_right.contains(right)
+        {
+            continue;
+        }
+
+        // a type matching two others equally well stays a removal and an addition
+        let is_ambiguous =
+            candidates
+                .iter()
+                .any(|(other_similarity, other_removed, other_added)| {
+                    other_similarity == similarity
+                        && (other_removed.name.node == removed_type.name.node)
+                            != (other_added.name.node == added_type.name.node)
+                });
+        if is_ambiguous {
+            ambiguous_left.insert(left);
+            ambiguous_right.insert(right);
+        } else {
+            renamed_left.insert(left);
+            renamed_right.insert(right);
+            renames.push(SchemaChange {
+                location: type_location.clone(),
+                kind: SchemaChangeKind::TypeRenamed {
+                    left: left.to_string(),
+                    right: right.to_string(),
+                },
+                change_type: ChangeType::Breaking,
+            });
+        }
+    }
+
+    changes
+        .into_iter()
+        .filter(|change| {
+            change.location != type_location
+                || match &change.kind {
+                    SchemaChangeKind::ItemRemoved { name } => !renamed_left.contains(name.as_str()),
+                    SchemaChangeKind::ItemAdded { name } => !renamed_right.contains(name.as_str()),
+                    _ => true,
+                }
+        })
+        .chain(renames)
+        .collect()
+}
+
+fn find_type<'a>(service_document: &'a ServiceDocument, name: &str) -> Option<&'a TypeDefinition> {
+    filter_types_of_service_document(service_document)
+        .find(|type_definition| type_definition.name.node.as_str() == name)
+}
+
+fn type_similarity(left: &TypeDefinition, right: &TypeDefinition) -> Option<f64> {
+    let members_left = type_members(&left.kind)?;
+    let members_right = type_members(&right.kind)?;
+
+    if std::mem::discriminant(&left.kind) != std::mem::discriminant(&right.kind) {
+        return None;
+    }
+
+    let shared = members_left.intersection(&members_right).count();
+    let all = members_left.union(&members_right).count();
+
+    Some(shared as f64 / all as f64)
+}
+
+fn type_members(kind: &TypeKind) -> Option<BTreeSet<String>> {
+    let members = match kind {
+        // scalars have no members, so there is nothing to match them on
+        TypeKind::Scalar => return None,
+        TypeKind::Object(object_type) => object_type
+            .fields
+            .iter()
+            .map(|field| format!("{}: {}", field.node.name.node, field.node.ty.node))
+            .collect(),
+        TypeKind::Interface(interface_type) => interface_type
+            .fields
+            .iter()
+            .map(|field| format!("{}: {}", field.node.name.node, field.node.ty.node))
+            .collect(),
+        TypeKind::Union(union_type) => union_type
+            .members
+            .iter()
+            .map(|member| member.node.to_string())
+            .collect(),
+        TypeKind::Enum(enum_type) => enum_type
+            .values
+            .iter()
+            .map(|value| value.node.value.node.to_string())
+            .collect(),
+        TypeKind::InputObject(input_object_type) => input_object_type
+            .fields
+            .iter()
+            .map(|field| format!("{}: {}", field.node.name.node, field.node.ty.node))
+            .collect::<BTreeSet<_>>(),
+    };
+
+    Some(members).filter(|members: &BTreeSet<String>| !members.is_empty())
+}
@@ -0,0 +1,81 @@
+use super::{diff_location::DiffLocation, ChangeType};
+
+#[derive(Debug, Clone)]
+pub enum SchemaChangeKind {
+    ItemAdded { name: String },
+    ItemRemoved { name: String },
+    TypeMismatch { name: String },
+    ValueChanged { left: String, right: String },
+    ValueAdded { value: String },
+    ValueRemoved { value: String },
+    TypeRenamed { left: String, right: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct SchemaChange {
+    pub location: DiffLocation,
+    pub kind: SchemaChangeKind,
+    pub change_type: ChangeType,
+}
+
+impl SchemaChange {
+    pub fn is_under_type(&self, type_name: &str) -> bool {
+        match self.location.root_name() {
+            Some(root_name) => root_name == type_name,
+            // added or removed top level definitions are reported without a name in the location
+            None => match &self.kind {
+                SchemaChangeKind::ItemAdded { name }
+                | SchemaChangeKind::ItemRemoved { name }
+                | SchemaChangeKind::TypeMismatch { name } => name == type_name,
+                SchemaChangeKind::TypeRenamed { left, right } => {
+                    left == type_name || right == type_name
+                }
+                SchemaChangeKind::ValueChanged { .. }
+                | SchemaChangeKind::ValueAdded { .. }
+                | SchemaChangeKind::ValueRemoved { .. } => false,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaChangeKind::ItemAdded { name } => {
+                write!(f, "item is added to the right, name = '{}'", name)
+            }
+            SchemaChangeKind::ItemRemoved { name } => {
+                write!(f, "item is removed, name = '{}'", name)
+            }
+            SchemaChangeKind::TypeMismatch { name } => {
+                write!(f, "type mismatch, name = '{}'", name)
+            }
+            SchemaChangeKind::ValueChanged { left, right } => {
+                write!(f, "left value = '{}', right value = '{}'", left, right)
+            }
+            SchemaChangeKind::ValueAdded { value } => {
+                write!(f, "added to right, value = '{}'", value)
+            }
+            SchemaChangeKind::ValueRemoved { value } => {
+                write!(f, "item is removed, value = '{}'", value)
+            }
+            SchemaChangeKind::TypeRenamed { left, right } => {
+                write!(
+                    f,
+                    "type is renamed, left name = '{}', right name = '{}'",
+                    left, right
+                )
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} (breaking = {})",
+            self.location, self.kind, self.change_type,
+        )
+    }
+}
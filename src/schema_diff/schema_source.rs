@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use async_graphql_parser::{parse_schema, types::ServiceDocument};
+use error::{CannotLoadSchemaSource, CannotLoadServiceDocumentFromGit, InvalidSchemaSource};
+
+use super::TryIntoServiceDocument;
+
+const GIT_PREFIX: &str = "git:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaSource {
+    Path(PathBuf),
+    // the path is relative to the current directory like the path of a file source
+    Git { revision: String, path: PathBuf },
+}
+
+impl std::str::FromStr for SchemaSource {
+    type Err = InvalidSchemaSource;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some(git_spec) = value.strip_prefix(GIT_PREFIX) else {
+            return Ok(Self::Path(value.into()));
+        };
+
+        match git_spec.split_once(':') {
+            Some((revision, path)) if !revision.is_empty() && !path.is_empty() => Ok(Self::Git {
+                revision: revision.to_string(),
+                path: path.into(),
+            }),
+            _ => Err(InvalidSchemaSource {
+                schema_source: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaSource::Path(path) => write!(f, "{}", path.display()),
+            SchemaSource::Git { revision, path } => {
+                write!(f, "{GIT_PREFIX}{revision}:{}", path.display())
+            }
+        }
+    }
+}
+
+impl TryIntoServiceDocument for SchemaSource {
+    type Error = CannotLoadSchemaSource;
+
+    fn try_into_service_document(self) -> Result<ServiceDocument, Self::Error> {
+        match self {
+            SchemaSource::Path(path) => Ok(path.try_into_service_document()?),
+            SchemaSource::Git { revision, path } => {
+                let failed = |error: Box<dyn std::error::Error>| CannotLoadServiceDocumentFromGit {
+                    revision: revision.clone(),
+                    path: path.clone(),
+                    error,
+                };
+
+                let contents = git_show(&revision, &path).map_err(failed)?;
+
+                Ok(parse_schema(contents).map_err(|e| failed(e.into()))?)
+            }
+        }
+    }
+}
+
+// `git show <revision>:./<path>` resolves the path relative to the current directory instead of the repository root
+fn git_show(revision: &str, path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let object = if path.is_absolute() || path.starts_with(".") {
+        format!("{revision}:{}", path.display())
+    } else {
+        format!("{revision}:./{}", path.display())
+    };
+
+    let output = std::process::Command::new("git")
+        .arg("show")
+        .arg(object)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "git is not available".into(),
+            _ => format!("cannot run git: {e}"),
+        })?;
+
+    if !output.status.success() {
+        // e.g. "fatal: invalid object name 'HEAD~5'" or "fatal: path 'schema.graphql' does not exist in 'HEAD'"
+        return Err(String::from_utf8_lossy(&output.stderr).trim().into());
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+pub mod error {
+    use std::path::PathBuf;
+
+    use crate::schema_diff::try_into_service_document::error::CannotLoadServiceDocumentFromPath;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidSchemaSource: schema source = '{schema_source}', expected a path or git:<revision>:<path>")]
+    pub struct InvalidSchemaSource {
+        pub schema_source: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error(
+        "CannotLoadServiceDocumentFromGit: revision = '{revision}', path = '{path}', error = {error}"
+    )]
+    pub struct CannotLoadServiceDocumentFromGit {
+        pub revision: String,
+        pub path: PathBuf,
+        pub error: Box<dyn std::error::Error>,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum CannotLoadSchemaSource {
+        #[error(transparent)]
+        Path(#[from] CannotLoadServiceDocumentFromPath),
+        #[error(transparent)]
+        Git(#[from] CannotLoadServiceDocumentFromGit),
+    }
+}
@@ -0,0 +1,97 @@
+use std::{path::Path, sync::Arc};
+
+use error::NoCertificates;
+use rustls::pki_types::{pem::PemObject, CertificateDer};
+
+const PEM_CERTIFICATE_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_CERTIFICATE_END: &str = "-----END CERTIFICATE-----";
+
+// the cacert file takes precedence over the SSL_CERT_FILE and SSL_CERT_DIR environment variables
+pub fn load_ca_certificates(
+    cacert: Option<&Path>,
+    from_env: bool,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if let Some(cacert) = cacert {
+        return certificates_of_file(cacert);
+    }
+
+    let mut certificates = Vec::new();
+    if !from_env {
+        return Ok(certificates);
+    }
+
+    if let Some(cert_file) = std::env::var_os("SSL_CERT_FILE") {
+        certificates.extend(certificates_of_file(Path::new(&cert_file))?);
+    }
+
+    if let Some(cert_dirs) = std::env::var_os("SSL_CERT_DIR") {
+        for cert_dir in std::env::split_paths(&cert_dirs) {
+            let mut paths = std::fs::read_dir(&cert_dir)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            paths.sort();
+
+            for path in paths.into_iter().filter(|path| path.is_file()) {
+                // the directories can contain other files, e.g., der encoded certificates
+                let contents = std::fs::read(&path)?;
+                certificates.extend(split_pem_certificates(&String::from_utf8_lossy(&contents)));
+            }
+        }
+    }
+
+    Ok(certificates)
+}
+
+// the ca certificates extend the bundled webpki roots like with the http client
+pub fn rustls_client_config(
+    ca_certificates: &[String],
+) -> Result<Arc<rustls::ClientConfig>, Box<dyn std::error::Error>> {
+    let mut root_store =
+        rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for ca_certificate in ca_certificates.iter() {
+        // the pem parser expects a line break after the end marker
+        let ca_certificate = format!("{ca_certificate}\n");
+        root_store.add(CertificateDer::from_pem_slice(ca_certificate.as_bytes())?)?;
+    }
+
+    let client_config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(root_store)
+    .with_no_client_auth();
+
+    Ok(Arc::new(client_config))
+}
+
+fn certificates_of_file(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let certificates = split_pem_certificates(&std::fs::read_to_string(path)?);
+
+    if certificates.is_empty() {
+        return Err(NoCertificates {
+            path: path.to_path_buf(),
+        }
+        .into());
+    }
+
+    Ok(certificates)
+}
+
+fn split_pem_certificates(pem: &str) -> Vec<String> {
+    pem.split_inclusive(PEM_CERTIFICATE_END)
+        .filter(|block| block.ends_with(PEM_CERTIFICATE_END))
+        .filter_map(|block| {
+            block
+                .find(PEM_CERTIFICATE_BEGIN)
+                .map(|begin_pos| block[begin_pos..].to_string())
+        })
+        .collect()
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("NoCertificates: path = '{path}', expected pem encoded certificates")]
+    pub struct NoCertificates {
+        pub path: std::path::PathBuf,
+    }
+}
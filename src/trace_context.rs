@@ -0,0 +1,79 @@
+use error::InvalidTraceId;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use uuid::Uuid;
+
+const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+const TRACESTATE: HeaderName = HeaderName::from_static("tracestate");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceId(String);
+
+impl TraceId {
+    pub fn random() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl std::str::FromStr for TraceId {
+    type Err = InvalidTraceId;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trace_id = value.to_ascii_lowercase();
+        // an all zero trace id is invalid according to the w3c trace context
+        if trace_id.len() != 32
+            || !trace_id.chars().all(|c| c.is_ascii_hexdigit())
+            || trace_id.chars().all(|c| c == '0')
+        {
+            return Err(InvalidTraceId {
+                trace_id: value.to_string(),
+            });
+        }
+
+        Ok(Self(trace_id))
+    }
+}
+
+impl std::fmt::Display for TraceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceContext {
+    pub trace_id: TraceId,
+    pub tracestate: Option<HeaderValue>,
+}
+
+impl TraceContext {
+    // every call is a new span of the same trace, so the retries show up as separate spans
+    pub fn insert_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(TRACEPARENT, self.traceparent());
+        if let Some(tracestate) = self.tracestate.as_ref() {
+            headers.insert(TRACESTATE, tracestate.clone());
+        }
+    }
+
+    fn traceparent(&self) -> HeaderValue {
+        // version 00, the sampled flag is set so the servers record the requests
+        let traceparent = format!("00-{}-{}-01", self.trace_id, random_span_id());
+
+        HeaderValue::from_str(&traceparent).expect("the traceparent consists of hex digits")
+    }
+}
+
+// the version nibble of a v4 uuid is in the first 8 bytes, so the span id is never all zeros
+fn random_span_id() -> String {
+    Uuid::new_v4().as_bytes()[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+pub mod error {
+    #[derive(Debug, thiserror::Error)]
+    #[error("InvalidTraceId: trace id = '{trace_id}', expected 32 hex digits, not all zeros")]
+    pub struct InvalidTraceId {
+        pub trace_id: String,
+    }
+}